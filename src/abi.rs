@@ -0,0 +1,954 @@
+use std::fmt;
+use std::str::FromStr;
+use ethereum_types::{H160, U256};
+use rustc_hex::ToHex;
+use error::Error;
+use signature::keccak256_hash;
+
+/// Size of an ABI word in bytes
+const WORD: usize = 32;
+/// Selector of `Error(string)`
+const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+/// Selector of `Panic(uint256)`
+const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+/// A Solidity ABI type
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParamType {
+    Address,
+    Bool,
+    /// Signed integer of the given number of bits
+    Int(usize),
+    /// Unsigned integer of the given number of bits
+    Uint(usize),
+    /// `bytesN`
+    FixedBytes(usize),
+    Bytes,
+    String,
+    /// `T[]`
+    Array(Box<ParamType>),
+    /// `T[k]`
+    FixedArray(Box<ParamType>, usize),
+    /// `(T1,T2,...)`
+    Tuple(Vec<ParamType>)
+}
+
+/// A value of a Solidity ABI type
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    Address(H160),
+    Bool(bool),
+    /// Signed integer in 256 bit two's complement form
+    Int(U256),
+    Uint(U256),
+    FixedBytes(Vec<u8>),
+    Bytes(Vec<u8>),
+    String(String),
+    Array(Vec<Token>),
+    FixedArray(Vec<Token>),
+    Tuple(Vec<Token>)
+}
+
+/// Reason a call reverted, decoded from its revert data
+#[derive(Debug, Clone, PartialEq)]
+pub enum Revert {
+    /// Reverted without data
+    Empty,
+    /// `Error(string)`, raised by `require` and `revert` with a message
+    Error(String),
+    /// `Panic(uint256)`, raised by failed assertions, arithmetic errors and the like
+    Panic(U256),
+    /// A custom error declared in the contract's ABI
+    Custom { name: String, args: Vec<Token> },
+    /// Revert data that is not understood
+    Unknown(Vec<u8>)
+}
+
+/// A function signature such as `transfer(address,uint256)`
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionSignature {
+    /// Function name
+    pub name: String,
+    /// Parameter types
+    pub inputs: Vec<ParamType>
+}
+
+impl ParamType {
+    /// Whether values of the type are encoded out of place, after the static part
+    pub fn is_dynamic(&self) -> bool {
+        match *self {
+            ParamType::Bytes | ParamType::String | ParamType::Array(_) => true,
+            ParamType::FixedArray(ref t, _) => t.is_dynamic(),
+            ParamType::Tuple(ref ts) => ts.iter().any(|t| t.is_dynamic()),
+            _ => false
+        }
+    }
+
+    /// Checks that `token` is a value of this type
+    pub fn type_check(&self, token: &Token) -> Result<(), Error> {
+        let ok = match (self, token) {
+            (ParamType::Address, Token::Address(_)) |
+            (ParamType::Bool, Token::Bool(_)) |
+            (ParamType::Bytes, Token::Bytes(_)) |
+            (ParamType::String, Token::String(_)) => true,
+            (ParamType::Uint(bits), Token::Uint(v)) => v.bits() <= *bits,
+            (ParamType::Int(bits), Token::Int(v)) => fits_signed(v, *bits),
+            (ParamType::FixedBytes(n), Token::FixedBytes(b)) => b.len() == *n,
+            (ParamType::Array(t), Token::Array(items)) => {
+                return items.iter().try_for_each(|item| t.type_check(item));
+            }
+            (ParamType::FixedArray(t, n), Token::FixedArray(items)) if items.len() == *n => {
+                return items.iter().try_for_each(|item| t.type_check(item));
+            }
+            (ParamType::Tuple(ts), Token::Tuple(items)) if ts.len() == items.len() => {
+                return ts.iter().zip(items.iter()).try_for_each(|(t, item)| t.type_check(item));
+            }
+            _ => false
+        };
+        if ok {
+            Ok(())
+        } else {
+            Err(Error::InvalidAbi(format!("{:?} is not a valid {}", token, self)))
+        }
+    }
+}
+
+/// Whether a two's complement value fits in a signed integer of `bits` bits
+fn fits_signed(v: &U256, bits: usize) -> bool {
+    if v.bit(255) {
+        (!*v).bits() < bits
+    } else {
+        v.bits() < bits
+    }
+}
+
+impl FromStr for ParamType {
+    type Err = Error;
+
+    /// Parses a canonical type such as `uint256`, `bytes32[]` or `(address,uint256)[2]`.
+    /// `uint` and `int` are taken as their 256 bit forms.
+    fn from_str(s: &str) -> Result<ParamType, Error> {
+        let invalid = || Error::InvalidAbi(format!("invalid type {}", s));
+        let s = s.trim();
+        if s.ends_with(']') {
+            let open = find_array_suffix(s).ok_or_else(invalid)?;
+            let element = Box::new(s[..open].parse()?);
+            let length = &s[open + 1..s.len() - 1];
+            return if length.is_empty() {
+                Ok(ParamType::Array(element))
+            } else if length.bytes().all(|b| b.is_ascii_digit()) {
+                Ok(ParamType::FixedArray(element, length.parse().map_err(|_| invalid())?))
+            } else {
+                Err(invalid())
+            };
+        }
+        if s.starts_with('(') && s.ends_with(')') {
+            let inner = &s[1..s.len() - 1];
+            let types = split_top_level(inner).ok_or_else(invalid)?
+                .into_iter()
+                .map(|t| t.parse())
+                .collect::<Result<Vec<_>, _>>()?;
+            return Ok(ParamType::Tuple(types));
+        }
+        match s {
+            "address" => Ok(ParamType::Address),
+            "bool" => Ok(ParamType::Bool),
+            "bytes" => Ok(ParamType::Bytes),
+            "string" => Ok(ParamType::String),
+            "uint" => Ok(ParamType::Uint(256)),
+            "int" => Ok(ParamType::Int(256)),
+            _ => {
+                if let Some(size) = s.strip_prefix("bytes") {
+                    parse_size(size).filter(|n| (1..=32).contains(n)).map(ParamType::FixedBytes).ok_or_else(invalid)
+                } else if let Some(bits) = s.strip_prefix("uint") {
+                    parse_size(bits).filter(|n| valid_bits(*n)).map(ParamType::Uint).ok_or_else(invalid)
+                } else if let Some(bits) = s.strip_prefix("int") {
+                    parse_size(bits).filter(|n| valid_bits(*n)).map(ParamType::Int).ok_or_else(invalid)
+                } else {
+                    Err(invalid())
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for ParamType {
+    /// Formats the canonical type name used in signatures
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParamType::Address => write!(f, "address"),
+            ParamType::Bool => write!(f, "bool"),
+            ParamType::Int(bits) => write!(f, "int{}", bits),
+            ParamType::Uint(bits) => write!(f, "uint{}", bits),
+            ParamType::FixedBytes(n) => write!(f, "bytes{}", n),
+            ParamType::Bytes => write!(f, "bytes"),
+            ParamType::String => write!(f, "string"),
+            ParamType::Array(ref t) => write!(f, "{}[]", t),
+            ParamType::FixedArray(ref t, n) => write!(f, "{}[{}]", t, n),
+            ParamType::Tuple(ref ts) => write!(f, "({})", join_types(ts))
+        }
+    }
+}
+
+impl Token {
+    /// Whether the token is encoded out of place, after the static part. A fixed array is
+    /// dynamic when its element type is; an empty one carries no element type and is taken
+    /// as static, so use `encode_typed` to encode e.g. a `string[0]`.
+    pub fn is_dynamic(&self) -> bool {
+        match *self {
+            Token::Bytes(_) | Token::String(_) | Token::Array(_) => true,
+            Token::FixedArray(ref items) => items.first().is_some_and(|t| t.is_dynamic()),
+            Token::Tuple(ref items) => items.iter().any(|t| t.is_dynamic()),
+            _ => false
+        }
+    }
+}
+
+impl fmt::Display for Token {
+    /// Formats the value for people to read: integers in decimal, bytes and addresses in
+    /// hex, strings quoted
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Token::Address(ref a) => write!(f, "{:?}", a),
+            Token::Bool(b) => write!(f, "{}", b),
+            Token::Uint(ref v) => write!(f, "{}", v),
+            Token::Int(ref v) if v.bit(255) => write!(f, "-{}", (!*v).overflowing_add(U256::one()).0),
+            Token::Int(ref v) => write!(f, "{}", v),
+            Token::FixedBytes(ref b) | Token::Bytes(ref b) => write!(f, "0x{}", b.to_hex::<String>()),
+            Token::String(ref s) => write!(f, "{:?}", s),
+            Token::Array(ref items) | Token::FixedArray(ref items) => write!(f, "[{}]", join_tokens(items)),
+            Token::Tuple(ref items) => write!(f, "({})", join_tokens(items))
+        }
+    }
+}
+
+impl Revert {
+    /// Describes a `Panic(uint256)` code as listed in the Solidity documentation
+    pub fn panic_description(code: &U256) -> Option<&'static str> {
+        if code.bits() > 8 {
+            return None;
+        }
+        let description = match code.low_u64() {
+            0x00 => "generic compiler inserted panic",
+            0x01 => "assertion failed",
+            0x11 => "arithmetic overflow or underflow",
+            0x12 => "division or modulo by zero",
+            0x21 => "invalid enum value",
+            0x22 => "incorrectly encoded storage byte array",
+            0x31 => "pop on empty array",
+            0x32 => "array index out of bounds",
+            0x41 => "out of memory",
+            0x51 => "call to uninitialized internal function",
+            _ => return None
+        };
+        Some(description)
+    }
+}
+
+impl fmt::Display for Revert {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Revert::Empty => write!(f, "reverted without reason"),
+            Revert::Error(ref reason) => write!(f, "reverted: {}", reason),
+            Revert::Panic(ref code) => match Revert::panic_description(code) {
+                Some(description) => write!(f, "panicked: {} (0x{:02x})", description, code.low_u64()),
+                None => write!(f, "panicked with code {:#x}", code)
+            },
+            Revert::Custom { ref name, ref args } => write!(f, "reverted: {}({})", name, join_tokens(args)),
+            Revert::Unknown(ref data) => write!(f, "reverted with data 0x{}", data.to_hex::<String>())
+        }
+    }
+}
+
+impl FunctionSignature {
+    /// The canonical signature, e.g. `transfer(address,uint256)`
+    pub fn signature(&self) -> String {
+        format!("{}({})", self.name, join_types(&self.inputs))
+    }
+
+    /// The first four bytes of the keccak256 hash of the signature
+    pub fn selector(&self) -> [u8; 4] {
+        let mut selector = [0u8; 4];
+        selector.copy_from_slice(&keccak256_hash(self.signature().as_bytes())[..4]);
+        selector
+    }
+
+    /// Returns the calldata calling the function with `args`, after checking that they
+    /// match the parameter types
+    pub fn encode_input(&self, args: &[Token]) -> Result<Vec<u8>, Error> {
+        if args.len() != self.inputs.len() {
+            return Err(Error::InvalidAbi(format!(
+                "{} takes {} arguments but {} were given", self.signature(), self.inputs.len(), args.len()
+            )));
+        }
+        let mut data = self.selector().to_vec();
+        data.extend_from_slice(&encode_typed(&self.inputs, args)?);
+        Ok(data)
+    }
+
+    /// Decodes the arguments of calldata calling this function
+    pub fn decode_input(&self, data: &[u8]) -> Result<Vec<Token>, Error> {
+        if data.len() < 4 || data[..4] != self.selector() {
+            return Err(Error::InvalidAbi(format!("calldata does not call {}", self.signature())));
+        }
+        decode(&self.inputs, &data[4..])
+    }
+}
+
+impl FromStr for FunctionSignature {
+    type Err = Error;
+
+    /// Parses a signature such as `transfer(address,uint256)`
+    fn from_str(s: &str) -> Result<FunctionSignature, Error> {
+        let invalid = || Error::InvalidAbi(format!("invalid function signature {}", s));
+        let s = s.trim();
+        let open = s.find('(').ok_or_else(invalid)?;
+        let name = s[..open].trim();
+        let is_identifier = name.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+        if !is_identifier {
+            return Err(invalid());
+        }
+        match s[open..].parse()? {
+            ParamType::Tuple(inputs) => Ok(FunctionSignature { name: name.to_string(), inputs }),
+            _ => Err(invalid())
+        }
+    }
+}
+
+/// ABI encodes `tokens` as the members of a tuple, as for function arguments
+pub fn encode(tokens: &[Token]) -> Vec<u8> {
+    let head_len: usize = tokens.iter().map(head_size).sum();
+    let mut head = Vec::with_capacity(head_len);
+    let mut tail = Vec::new();
+    for token in tokens.iter() {
+        if token.is_dynamic() {
+            head.extend_from_slice(&uint_word(U256::from(head_len + tail.len())));
+            tail.extend_from_slice(&encode_token(token));
+        } else {
+            head.extend_from_slice(&encode_token(token));
+        }
+    }
+    head.extend_from_slice(&tail);
+    head
+}
+
+/// ABI encodes `tokens` as values of `types`, after checking that they match. Which values
+/// go out of place follows from the types, as in `decode`, so this also handles empty fixed
+/// arrays of dynamic types that `encode` cannot tell apart.
+pub fn encode_typed(types: &[ParamType], tokens: &[Token]) -> Result<Vec<u8>, Error> {
+    if types.len() != tokens.len() {
+        return Err(Error::InvalidAbi(format!("{} values given for {} types", tokens.len(), types.len())));
+    }
+    for (t, token) in types.iter().zip(tokens.iter()) {
+        t.type_check(token)?;
+    }
+    Ok(encode_tuple(types, tokens))
+}
+
+/// Returns calldata calling the function with the signature `signature` with `args`
+pub fn encode_call(signature: &str, args: &[Token]) -> Result<Vec<u8>, Error> {
+    signature.parse::<FunctionSignature>()?.encode_input(args)
+}
+
+/// Decodes ABI encoded data holding values of `types`, such as function arguments or
+/// return data
+pub fn decode(types: &[ParamType], data: &[u8]) -> Result<Vec<Token>, Error> {
+    // values that do not overlap never decode to more bytes than the data holds
+    let mut budget = data.len();
+    decode_tuple(types, data, 0, &mut budget)
+}
+
+/// Decodes revert data carrying `Error(string)` or `Panic(uint256)`; anything else is
+/// returned as `Revert::Unknown`
+pub fn decode_revert(data: &[u8]) -> Result<Revert, Error> {
+    if data.is_empty() {
+        return Ok(Revert::Empty);
+    }
+    if data.len() >= 4 && data[..4] == ERROR_SELECTOR {
+        if let Token::String(reason) = decode(&[ParamType::String], &data[4..])?.remove(0) {
+            return Ok(Revert::Error(reason));
+        }
+    } else if data.len() >= 4 && data[..4] == PANIC_SELECTOR {
+        if let Token::Uint(code) = decode(&[ParamType::Uint(256)], &data[4..])?.remove(0) {
+            return Ok(Revert::Panic(code));
+        }
+    }
+    Ok(Revert::Unknown(data.to_vec()))
+}
+
+fn decode_tuple(types: &[ParamType], data: &[u8], base: usize, budget: &mut usize) -> Result<Vec<Token>, Error> {
+    let mut tokens = Vec::with_capacity(types.len());
+    let mut cursor = base;
+    for t in types.iter() {
+        if t.is_dynamic() {
+            let offset = read_usize(data, cursor)?;
+            let position = base.checked_add(offset).ok_or_else(out_of_bounds)?;
+            tokens.push(decode_token(t, data, position, budget)?);
+            cursor += WORD;
+        } else {
+            tokens.push(decode_token(t, data, cursor, budget)?);
+            cursor += static_size(t).ok_or_else(out_of_bounds)?;
+        }
+    }
+    Ok(tokens)
+}
+
+fn decode_token(t: &ParamType, data: &[u8], position: usize, budget: &mut usize) -> Result<Token, Error> {
+    let invalid = |what: &str| Error::InvalidAbi(format!("invalid {} at offset {}", what, position));
+    match *t {
+        // charged for their contents, by read_bytes and by the elements
+        ParamType::Bytes | ParamType::String | ParamType::FixedArray(..) | ParamType::Tuple(..) => {}
+        // a static value or an array length takes one word of output
+        _ => charge(budget, WORD)?
+    }
+    match *t {
+        ParamType::Address => {
+            let word = read_word(data, position)?;
+            if word[..12].iter().any(|b| *b != 0) {
+                return Err(invalid("address"));
+            }
+            Ok(Token::Address(H160::from(&word[12..])))
+        }
+        ParamType::Bool => match U256::from(read_word(data, position)?) {
+            v if v.is_zero() => Ok(Token::Bool(false)),
+            v if v == U256::one() => Ok(Token::Bool(true)),
+            _ => Err(invalid("bool"))
+        },
+        ParamType::Uint(bits) => {
+            let v = U256::from(read_word(data, position)?);
+            if v.bits() > bits {
+                return Err(invalid(&t.to_string()));
+            }
+            Ok(Token::Uint(v))
+        }
+        ParamType::Int(bits) => {
+            let v = U256::from(read_word(data, position)?);
+            if !fits_signed(&v, bits) {
+                return Err(invalid(&t.to_string()));
+            }
+            Ok(Token::Int(v))
+        }
+        ParamType::FixedBytes(n) => {
+            let word = read_word(data, position)?;
+            if word[n..].iter().any(|b| *b != 0) {
+                return Err(invalid(&t.to_string()));
+            }
+            Ok(Token::FixedBytes(word[..n].to_vec()))
+        }
+        ParamType::Bytes => Ok(Token::Bytes(read_bytes(data, position, budget)?.to_vec())),
+        ParamType::String => {
+            let bytes = read_bytes(data, position, budget)?;
+            String::from_utf8(bytes.to_vec()).map(Token::String).map_err(|_| invalid("UTF-8 string"))
+        }
+        ParamType::Array(ref element) => {
+            let length = read_usize(data, position)?;
+            // every element takes at least one word, which bounds what is allocated
+            if length > data.len().saturating_sub(position + WORD) / WORD {
+                return Err(out_of_bounds());
+            }
+            let types = vec![(**element).clone(); length];
+            Ok(Token::Array(decode_tuple(&types, data, position + WORD, budget)?))
+        }
+        ParamType::FixedArray(ref element, length) => {
+            if length > data.len().saturating_sub(position) / WORD {
+                return Err(out_of_bounds());
+            }
+            let types = vec![(**element).clone(); length];
+            Ok(Token::FixedArray(decode_tuple(&types, data, position, budget)?))
+        }
+        ParamType::Tuple(ref types) => Ok(Token::Tuple(decode_tuple(types, data, position, budget)?))
+    }
+}
+
+fn read_word(data: &[u8], position: usize) -> Result<&[u8], Error> {
+    position.checked_add(WORD)
+        .and_then(|end| data.get(position..end))
+        .ok_or_else(out_of_bounds)
+}
+
+fn read_usize(data: &[u8], position: usize) -> Result<usize, Error> {
+    let v = U256::from(read_word(data, position)?);
+    if v.bits() > 32 {
+        return Err(out_of_bounds());
+    }
+    Ok(v.low_u64() as usize)
+}
+
+fn read_bytes<'a>(data: &'a [u8], position: usize, budget: &mut usize) -> Result<&'a [u8], Error> {
+    let length = read_usize(data, position)?;
+    let start = position + WORD;
+    let bytes = data.get(start..start + length).ok_or_else(out_of_bounds)?;
+    charge(budget, WORD + length)?;
+    Ok(bytes)
+}
+
+/// Takes `size` bytes of decoded output off the budget, so that values pointing at the
+/// same data over and over cannot decode to far more than the data holds
+fn charge(budget: &mut usize, size: usize) -> Result<(), Error> {
+    *budget = budget.checked_sub(size)
+        .ok_or_else(|| Error::InvalidAbi("decoded values take more space than the data".to_string()))?;
+    Ok(())
+}
+
+/// Size of a static type in the static part of its enclosing tuple
+fn static_size(t: &ParamType) -> Option<usize> {
+    match *t {
+        ParamType::FixedArray(ref element, length) => static_size(element)?.checked_mul(length),
+        ParamType::Tuple(ref types) => types.iter().try_fold(0usize, |sum, t| sum.checked_add(static_size(t)?)),
+        _ => Some(WORD)
+    }
+}
+
+fn out_of_bounds() -> Error {
+    Error::InvalidAbi("data too short or offset out of bounds".to_string())
+}
+
+fn encode_token(token: &Token) -> Vec<u8> {
+    match *token {
+        Token::Address(ref address) => {
+            let mut word = vec![0u8; WORD];
+            word[12..].copy_from_slice(address);
+            word
+        }
+        Token::Bool(b) => uint_word(U256::from(b as u64)),
+        Token::Int(ref v) | Token::Uint(ref v) => uint_word(*v),
+        Token::FixedBytes(ref bytes) => pad_right(bytes),
+        Token::Bytes(ref bytes) => {
+            let mut out = uint_word(U256::from(bytes.len()));
+            out.extend_from_slice(&pad_right(bytes));
+            out
+        }
+        Token::String(ref s) => encode_token(&Token::Bytes(s.as_bytes().to_vec())),
+        Token::Array(ref items) => {
+            let mut out = uint_word(U256::from(items.len()));
+            out.extend_from_slice(&encode(items));
+            out
+        }
+        Token::FixedArray(ref items) | Token::Tuple(ref items) => encode(items)
+    }
+}
+
+fn encode_tuple(types: &[ParamType], tokens: &[Token]) -> Vec<u8> {
+    let head_len: usize = types.iter().zip(tokens.iter()).map(|(t, token)| typed_head_size(t, token)).sum();
+    let mut head = Vec::with_capacity(head_len);
+    let mut tail = Vec::new();
+    for (t, token) in types.iter().zip(tokens.iter()) {
+        if t.is_dynamic() {
+            head.extend_from_slice(&uint_word(U256::from(head_len + tail.len())));
+            tail.extend_from_slice(&encode_typed_token(t, token));
+        } else {
+            head.extend_from_slice(&encode_typed_token(t, token));
+        }
+    }
+    head.extend_from_slice(&tail);
+    head
+}
+
+fn encode_typed_token(t: &ParamType, token: &Token) -> Vec<u8> {
+    match (t, token) {
+        (ParamType::Array(element), Token::Array(items)) => {
+            let mut out = uint_word(U256::from(items.len()));
+            out.extend_from_slice(&encode_tuple(&vec![(**element).clone(); items.len()], items));
+            out
+        }
+        (ParamType::FixedArray(element, _), Token::FixedArray(items)) => {
+            encode_tuple(&vec![(**element).clone(); items.len()], items)
+        }
+        (ParamType::Tuple(types), Token::Tuple(items)) => encode_tuple(types, items),
+        _ => encode_token(token)
+    }
+}
+
+/// Size of a value of type `t` in the static part of its enclosing tuple
+fn typed_head_size(t: &ParamType, token: &Token) -> usize {
+    match (t, token) {
+        _ if t.is_dynamic() => WORD,
+        (ParamType::FixedArray(element, _), Token::FixedArray(items)) => {
+            items.iter().map(|item| typed_head_size(element, item)).sum()
+        }
+        (ParamType::Tuple(types), Token::Tuple(items)) => {
+            types.iter().zip(items.iter()).map(|(t, item)| typed_head_size(t, item)).sum()
+        }
+        _ => WORD
+    }
+}
+
+/// Size of a token in the static part of its enclosing tuple
+fn head_size(token: &Token) -> usize {
+    match *token {
+        _ if token.is_dynamic() => WORD,
+        Token::FixedArray(ref items) | Token::Tuple(ref items) => items.iter().map(head_size).sum(),
+        _ => WORD
+    }
+}
+
+fn uint_word(v: U256) -> Vec<u8> {
+    let word: [u8; 32] = v.into();
+    word.to_vec()
+}
+
+/// Pads to a multiple of the word size with trailing zeros
+fn pad_right(bytes: &[u8]) -> Vec<u8> {
+    let mut out = bytes.to_vec();
+    out.resize(bytes.len().div_ceil(WORD) * WORD, 0);
+    out
+}
+
+fn join_tokens(tokens: &[Token]) -> String {
+    tokens.iter().map(|t| t.to_string()).collect::<Vec<_>>().join(", ")
+}
+
+fn join_types(types: &[ParamType]) -> String {
+    types.iter().map(|t| t.to_string()).collect::<Vec<_>>().join(",")
+}
+
+fn valid_bits(bits: usize) -> bool {
+    bits > 0 && bits <= 256 && bits % 8 == 0
+}
+
+fn parse_size(size: &str) -> Option<usize> {
+    if size.is_empty() || size.starts_with('0') || !size.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    size.parse().ok()
+}
+
+/// Finds the `[` opening the outermost array suffix of a type ending in `]`
+fn find_array_suffix(s: &str) -> Option<usize> {
+    let open = s.rfind('[')?;
+    // the suffix must come after any tuple
+    if s[open..].contains(')') || open == 0 {
+        None
+    } else {
+        Some(open)
+    }
+}
+
+/// Splits a comma separated list of types, ignoring commas inside nested tuples
+fn split_top_level(s: &str) -> Option<Vec<&str>> {
+    if s.trim().is_empty() {
+        return Some(vec![]);
+    }
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.checked_sub(1)?,
+            ',' if depth == 0 => {
+                parts.push(&s[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if depth != 0 {
+        return None;
+    }
+    parts.push(&s[start..]);
+    Some(parts)
+}
+
+mod test {
+
+    #[test]
+    fn test_parses_signatures() {
+        use abi::{FunctionSignature, ParamType};
+
+        let f: FunctionSignature = "transfer(address,uint256)".parse().unwrap();
+        assert_eq!("transfer", f.name);
+        assert_eq!(vec![ParamType::Address, ParamType::Uint(256)], f.inputs);
+        assert_eq!([0xa9, 0x05, 0x9c, 0xbb], f.selector());
+
+        let f: FunctionSignature = "h((uint,bytes)[], address)".parse().unwrap();
+        assert_eq!("h((uint256,bytes)[],address)", f.signature());
+        assert_eq!([0x16, 0xf9, 0xdb, 0x22], f.selector());
+        assert_eq!("f()", "f()".parse::<FunctionSignature>().unwrap().signature());
+        assert_eq!(
+            ParamType::FixedArray(Box::new(ParamType::Array(Box::new(ParamType::FixedBytes(3)))), 2),
+            "bytes3[][2]".parse().unwrap()
+        );
+
+        for invalid in ["transfer", "(uint256)", "f(uint7)", "f(uint264)", "f(bytes0)", "f(bytes33)",
+                        "f(uint256", "f((uint256)", "f(uint256[x])", "f(foo)", "f(uint256,)", "1f()"].iter() {
+            assert!(invalid.parse::<FunctionSignature>().is_err(), "{} should not parse", invalid);
+        }
+    }
+
+    #[test]
+    fn test_encodes_solidity_examples() {
+        use ethereum_types::*;
+        use rustc_hex::FromHex;
+        use abi::{encode_call, Token};
+
+        // examples from the Solidity ABI specification
+        let expected: Vec<u8> = "cdcd77c0\
+            0000000000000000000000000000000000000000000000000000000000000045\
+            0000000000000000000000000000000000000000000000000000000000000001".from_hex().unwrap();
+        assert_eq!(expected, encode_call("baz(uint32,bool)", &[Token::Uint(U256::from(69)), Token::Bool(true)]).unwrap());
+
+        let expected: Vec<u8> = "fce353f6\
+            6162630000000000000000000000000000000000000000000000000000000000\
+            6465660000000000000000000000000000000000000000000000000000000000".from_hex().unwrap();
+        let args = [Token::FixedArray(vec![Token::FixedBytes(b"abc".to_vec()), Token::FixedBytes(b"def".to_vec())])];
+        assert_eq!(expected, encode_call("bar(bytes3[2])", &args).unwrap());
+
+        let expected: Vec<u8> = "a5643bf2\
+            0000000000000000000000000000000000000000000000000000000000000060\
+            0000000000000000000000000000000000000000000000000000000000000001\
+            00000000000000000000000000000000000000000000000000000000000000a0\
+            0000000000000000000000000000000000000000000000000000000000000004\
+            6461766500000000000000000000000000000000000000000000000000000000\
+            0000000000000000000000000000000000000000000000000000000000000003\
+            0000000000000000000000000000000000000000000000000000000000000001\
+            0000000000000000000000000000000000000000000000000000000000000002\
+            0000000000000000000000000000000000000000000000000000000000000003".from_hex().unwrap();
+        let args = [
+            Token::Bytes(b"dave".to_vec()),
+            Token::Bool(true),
+            Token::Array(vec![Token::Uint(U256::from(1)), Token::Uint(U256::from(2)), Token::Uint(U256::from(3))])
+        ];
+        assert_eq!(expected, encode_call("sam(bytes,bool,uint256[])", &args).unwrap());
+
+        let expected: Vec<u8> = "8be65246\
+            0000000000000000000000000000000000000000000000000000000000000123\
+            0000000000000000000000000000000000000000000000000000000000000080\
+            3132333435363738393000000000000000000000000000000000000000000000\
+            00000000000000000000000000000000000000000000000000000000000000e0\
+            0000000000000000000000000000000000000000000000000000000000000002\
+            0000000000000000000000000000000000000000000000000000000000000456\
+            0000000000000000000000000000000000000000000000000000000000000789\
+            000000000000000000000000000000000000000000000000000000000000000d\
+            48656c6c6f2c20776f726c642100000000000000000000000000000000000000".from_hex().unwrap();
+        let args = [
+            Token::Uint(U256::from(0x123)),
+            Token::Array(vec![Token::Uint(U256::from(0x456)), Token::Uint(U256::from(0x789))]),
+            Token::FixedBytes(b"1234567890".to_vec()),
+            Token::Bytes(b"Hello, world!".to_vec())
+        ];
+        assert_eq!(expected, encode_call("f(uint256,uint32[],bytes10,bytes)", &args).unwrap());
+
+        let expected: Vec<u8> = "2289b18c\
+            0000000000000000000000000000000000000000000000000000000000000040\
+            0000000000000000000000000000000000000000000000000000000000000140\
+            0000000000000000000000000000000000000000000000000000000000000002\
+            0000000000000000000000000000000000000000000000000000000000000040\
+            00000000000000000000000000000000000000000000000000000000000000a0\
+            0000000000000000000000000000000000000000000000000000000000000002\
+            0000000000000000000000000000000000000000000000000000000000000001\
+            0000000000000000000000000000000000000000000000000000000000000002\
+            0000000000000000000000000000000000000000000000000000000000000001\
+            0000000000000000000000000000000000000000000000000000000000000003\
+            0000000000000000000000000000000000000000000000000000000000000003\
+            0000000000000000000000000000000000000000000000000000000000000060\
+            00000000000000000000000000000000000000000000000000000000000000a0\
+            00000000000000000000000000000000000000000000000000000000000000e0\
+            0000000000000000000000000000000000000000000000000000000000000003\
+            6f6e650000000000000000000000000000000000000000000000000000000000\
+            0000000000000000000000000000000000000000000000000000000000000003\
+            74776f0000000000000000000000000000000000000000000000000000000000\
+            0000000000000000000000000000000000000000000000000000000000000005\
+            7468726565000000000000000000000000000000000000000000000000000000".from_hex().unwrap();
+        let args = [
+            Token::Array(vec![
+                Token::Array(vec![Token::Uint(U256::from(1)), Token::Uint(U256::from(2))]),
+                Token::Array(vec![Token::Uint(U256::from(3))])
+            ]),
+            Token::Array(vec![
+                Token::String("one".to_string()),
+                Token::String("two".to_string()),
+                Token::String("three".to_string())
+            ])
+        ];
+        assert_eq!(expected, encode_call("g(uint256[][],string[])", &args).unwrap());
+    }
+
+    #[test]
+    fn test_encodes_static_tuples_inline() {
+        use ethereum_types::*;
+        use abi::{encode, Token};
+
+        let tuple = Token::Tuple(vec![Token::Uint(U256::from(1)), Token::Address(H160::from(2))]);
+        let encoded = encode(&[tuple, Token::Bytes(vec![0xff])]);
+        // two words of tuple and the offset of the bytes, then their length and data
+        assert_eq!(5 * 32, encoded.len());
+        assert_eq!(U256::from(1), U256::from(&encoded[..32]));
+        assert_eq!(U256::from(2), U256::from(&encoded[32..64]));
+        assert_eq!(U256::from(96), U256::from(&encoded[64..96]));
+    }
+
+    #[test]
+    fn test_type_checks_arguments() {
+        use ethereum_types::*;
+        use abi::{encode_call, Token};
+
+        assert!(encode_call("f(uint8)", &[Token::Uint(U256::from(256))]).is_err());
+        assert!(encode_call("f(uint8)", &[Token::Uint(U256::from(255))]).is_ok());
+        assert!(encode_call("f(int8)", &[Token::Int(!U256::from(127))]).is_ok());
+        assert!(encode_call("f(int8)", &[Token::Int(!U256::from(128))]).is_err());
+        assert!(encode_call("f(int8)", &[Token::Int(U256::from(128))]).is_err());
+        assert!(encode_call("f(bytes2)", &[Token::FixedBytes(vec![1])]).is_err());
+        assert!(encode_call("f(address)", &[Token::Uint(U256::from(1))]).is_err());
+        assert!(encode_call("f(uint256[2])", &[Token::FixedArray(vec![Token::Uint(U256::zero())])]).is_err());
+        assert!(encode_call("f(uint256)", &[]).is_err());
+    }
+
+    #[test]
+    fn test_sets_transaction_data() {
+        use ethereum_types::*;
+        use abi::Token;
+        use raw_transaction::RawTransaction;
+
+        let mut tx = RawTransaction::default();
+        tx.set_call_data(
+            "transfer(address,uint256)",
+            &[Token::Address(H160::from(0x35)), Token::Uint(U256::from(1000))]
+        ).unwrap();
+        assert_eq!(4 + 64, tx.data.len());
+        assert_eq!(&[0xa9, 0x05, 0x9c, 0xbb], &tx.data[..4]);
+        assert!(tx.set_call_data("transfer(address,uint256)", &[]).is_err());
+        assert_eq!(4 + 64, tx.data.len());
+    }
+
+    #[test]
+    fn test_decodes_what_it_encodes() {
+        use ethereum_types::*;
+        use abi::{decode, encode, FunctionSignature, Token};
+
+        let f: FunctionSignature = "f(uint256,uint32[],bytes10,bytes,(int8,string)[2],bool,address)".parse().unwrap();
+        let args = vec![
+            Token::Uint(U256::from(0x123)),
+            Token::Array(vec![Token::Uint(U256::from(0x456)), Token::Uint(U256::from(0x789))]),
+            Token::FixedBytes(b"1234567890".to_vec()),
+            Token::Bytes(b"Hello, world!".to_vec()),
+            Token::FixedArray(vec![
+                Token::Tuple(vec![Token::Int(!U256::zero()), Token::String("one".to_string())]),
+                Token::Tuple(vec![Token::Int(U256::from(127)), Token::String("".to_string())])
+            ]),
+            Token::Bool(true),
+            Token::Address(H160::from(0x35))
+        ];
+        let data = f.encode_input(&args).unwrap();
+        assert_eq!(args, f.decode_input(&data).unwrap());
+        assert_eq!(args, decode(&f.inputs, &encode(&args)).unwrap());
+        assert!(f.decode_input(&data[..data.len() - 1]).is_err());
+        assert!("g(uint256)".parse::<FunctionSignature>().unwrap().decode_input(&data).is_err());
+    }
+
+    #[test]
+    fn test_encodes_empty_fixed_arrays_by_type() {
+        use ethereum_types::*;
+        use abi::{decode, encode_typed, FunctionSignature, ParamType, Token};
+
+        // string[0] holds no values but is dynamic by its element type
+        let f: FunctionSignature = "f(string[0],uint256,(bytes,uint8)[0])".parse().unwrap();
+        assert!(f.inputs[0].is_dynamic() && f.inputs[2].is_dynamic());
+        let args = vec![Token::FixedArray(vec![]), Token::Uint(U256::from(7)), Token::FixedArray(vec![])];
+        let data = f.encode_input(&args).unwrap();
+        assert_eq!(4 + 3 * 32, data.len());
+        assert_eq!(U256::from(96), U256::from(&data[4..36]));
+        assert_eq!(U256::from(96), U256::from(&data[68..100]));
+        assert_eq!(args, f.decode_input(&data).unwrap());
+
+        // of a static element type it stays inline and takes no space
+        let types = [ParamType::FixedArray(Box::new(ParamType::Uint(256)), 0), ParamType::Uint(256)];
+        let encoded = encode_typed(&types, &args[..2]).unwrap();
+        assert_eq!(32, encoded.len());
+        assert_eq!(args[..2].to_vec(), decode(&types, &encoded).unwrap());
+        assert!(encode_typed(&types, &args).is_err());
+
+        let strings = Token::FixedArray(vec![Token::String("a".to_string())]);
+        assert_eq!(ParamType::FixedArray(Box::new(ParamType::String), 1).is_dynamic(), strings.is_dynamic());
+    }
+
+    #[test]
+    fn test_rejects_malformed_data() {
+        use rustc_hex::FromHex;
+        use abi::{decode, ParamType};
+
+        let word = |hex: &str| -> Vec<u8> { format!("{:0>64}", hex).from_hex().unwrap() };
+        assert!(decode(&[ParamType::Bool], &word("2")).is_err());
+        assert!(decode(&[ParamType::Uint(8)], &word("100")).is_err());
+        assert!(decode(&[ParamType::Int(8)], &word("80")).is_err());
+        assert!(decode(&[ParamType::Int(8)], &[0xff; 32]).is_ok());
+        assert!(decode(&[ParamType::Address], &word("1000000000000000000000000000000000000000000")).is_err());
+        assert!(decode(&[ParamType::FixedBytes(1)], &word("1")).is_err());
+        // offsets and lengths pointing past the end
+        assert!(decode(&[ParamType::Bytes], &word("20")).is_err());
+        assert!(decode(&[ParamType::Bytes], &[word("20"), word("21")].concat()).is_err());
+        assert!(decode(&[ParamType::Array(Box::new(ParamType::Uint(256)))], &[word("20"), word("ffffffff")].concat()).is_err());
+        assert!(decode(&[ParamType::String], &[word("20"), word("1"), vec![0xff; 32]].concat()).is_err());
+    }
+
+    #[test]
+    fn test_rejects_overlapping_values() {
+        use rustc_hex::FromHex;
+        use abi::{decode, ParamType, Token};
+
+        let word = |hex: &str| -> Vec<u8> { format!("{:0>64}", hex).from_hex().unwrap() };
+        let types = [ParamType::Array(Box::new(ParamType::Bytes))];
+
+        // 64 elements all pointing at the same 1 KiB
+        let mut data = [word("20"), word("40")].concat();
+        for _ in 0..64 {
+            data.extend(word("800"));
+        }
+        data.extend(word("400"));
+        data.extend(vec![0xab; 1024]);
+        assert!(decode(&types, &data).is_err());
+
+        // sharing is fine while the values fit in the data
+        let data = [word("20"), word("2"), word("40"), word("40"), word("1"), word("0")].concat();
+        assert_eq!(
+            vec![Token::Array(vec![Token::Bytes(vec![0]), Token::Bytes(vec![0])])],
+            decode(&types, &data).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_decodes_revert_reasons() {
+        use ethereum_types::*;
+        use rustc_hex::FromHex;
+        use abi::{decode_revert, Revert};
+
+        // require(false, "Not enough Ether provided.")
+        let data: Vec<u8> = "08c379a0\
+            0000000000000000000000000000000000000000000000000000000000000020\
+            000000000000000000000000000000000000000000000000000000000000001a\
+            4e6f7420656e6f7567682045746865722070726f76696465642e000000000000".from_hex().unwrap();
+        let revert = decode_revert(&data).unwrap();
+        assert_eq!(Revert::Error("Not enough Ether provided.".to_string()), revert);
+        assert_eq!("reverted: Not enough Ether provided.", revert.to_string());
+
+        let data: Vec<u8> = "4e487b71\
+            0000000000000000000000000000000000000000000000000000000000000011".from_hex().unwrap();
+        let revert = decode_revert(&data).unwrap();
+        assert_eq!(Revert::Panic(U256::from(0x11)), revert);
+        assert_eq!("panicked: arithmetic overflow or underflow (0x11)", revert.to_string());
+
+        assert_eq!(Revert::Empty, decode_revert(&[]).unwrap());
+        assert_eq!(Revert::Unknown(vec![1, 2, 3, 4]), decode_revert(&[1, 2, 3, 4]).unwrap());
+        assert!(decode_revert(&data[..20]).is_err());
+    }
+
+    #[test]
+    fn test_formats_tokens() {
+        use ethereum_types::*;
+        use abi::Token;
+
+        let token = Token::Tuple(vec![
+            Token::Address(H160::from(0x35)),
+            Token::Int(!U256::from(4)),
+            Token::Uint(U256::from(1000)),
+            Token::Array(vec![Token::Bytes(vec![0xca, 0xfe]), Token::Bytes(vec![])]),
+            Token::String("hi \"there\"".to_string()),
+            Token::Bool(false)
+        ]);
+        assert_eq!(
+            "(0x0000000000000000000000000000000000000035, -5, 1000, [0xcafe, 0x], \"hi \\\"there\\\"\", false)",
+            token.to_string()
+        );
+    }
+}
@@ -0,0 +1,83 @@
+use ethereum_types::{H160, H256};
+use rlp::{Decodable, DecoderError, Encodable, Rlp, RlpStream};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Addresses and storage keys a transaction plans to access, as introduced by EIP-2930.
+///
+/// Serialized in the same shape as the geth JSON `accessList` field.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AccessList(pub Vec<(H160, Vec<H256>)>);
+
+#[derive(Deserialize, Serialize)]
+struct AccessListItem {
+    address: H160,
+    #[serde(rename = "storageKeys")]
+    storage_keys: Vec<H256>
+}
+
+impl Encodable for AccessList {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.begin_list(self.0.len());
+        for (address, storage_keys) in self.0.iter() {
+            s.begin_list(2);
+            s.append(address);
+            s.append_list(storage_keys);
+        }
+    }
+}
+
+impl Decodable for AccessList {
+    fn decode(rlp: &Rlp) -> Result<AccessList, DecoderError> {
+        let mut items = vec![];
+        for item in rlp.iter() {
+            if item.item_count()? != 2 {
+                return Err(DecoderError::RlpIncorrectListLen);
+            }
+            items.push((item.val_at(0)?, item.list_at(1)?));
+        }
+        Ok(AccessList(items))
+    }
+}
+
+impl From<Vec<(H160, Vec<H256>)>> for AccessList {
+    fn from(items: Vec<(H160, Vec<H256>)>) -> AccessList {
+        AccessList(items)
+    }
+}
+
+impl Serialize for AccessList {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let items: Vec<AccessListItem> = self.0.iter()
+            .map(|(address, storage_keys)| AccessListItem {
+                address: *address,
+                storage_keys: storage_keys.clone()
+            })
+            .collect();
+        items.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for AccessList {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<AccessList, D::Error> {
+        let items: Vec<AccessListItem> = Deserialize::deserialize(deserializer)?;
+        Ok(AccessList(items.into_iter().map(|i| (i.address, i.storage_keys)).collect()))
+    }
+}
+
+mod test {
+
+    #[test]
+    fn test_access_list_geth_json() {
+        use ethereum_types::*;
+        use access_list::AccessList;
+        use serde_json;
+
+        let json = r#"[{"address":"0xde0b295669a9fd93d5f28d9ec85e40f4cb697bae","storageKeys":["0x0000000000000000000000000000000000000000000000000000000000000003","0x0000000000000000000000000000000000000000000000000000000000000007"]},{"address":"0xbb9bc244d798123fde783fcc1c72d3bb8c189413","storageKeys":[]}]"#;
+        let access_list: AccessList = serde_json::from_str(json).unwrap();
+        assert_eq!(access_list, AccessList(vec![
+            (H160::from("0xde0b295669a9fd93d5f28d9ec85e40f4cb697bae"), vec![H256::from(3), H256::from(7)]),
+            (H160::from("0xbb9bc244d798123fde783fcc1c72d3bb8c189413"), vec![])
+        ]));
+        assert_eq!(json, serde_json::to_string(&access_list).unwrap());
+    }
+}
@@ -0,0 +1,271 @@
+use std::fmt;
+use std::str::FromStr;
+use ethereum_types::{H160, H256, U256};
+use rlp::RlpStream;
+use rustc_hex::{FromHex, ToHex};
+use secp256k1::key::{PublicKey, SecretKey};
+use secp256k1::Secp256k1;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use tiny_keccak::keccak256;
+use error::Error;
+
+/// An account address that formats with its EIP-55 checksum and validates the checksum of
+/// mixed-case input. All lowercase or all uppercase input carries no checksum and is
+/// accepted as is.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Address(pub H160);
+
+impl Address {
+    /// Formats the address with its EIP-55 checksum, or with the EIP-1191 checksum of
+    /// `chain_id` when one is given
+    pub fn to_checksum(&self, chain_id: Option<u64>) -> String {
+        let hex: String = self.0.to_hex();
+        let hash = keccak256(checksum_preimage(&hex, chain_id).as_bytes());
+        let checksummed: String = hex.chars().enumerate().map(|(i, c)| {
+            let nibble = if i % 2 == 0 { hash[i / 2] >> 4 } else { hash[i / 2] & 0x0f };
+            if nibble >= 8 { c.to_ascii_uppercase() } else { c }
+        }).collect();
+        format!("0x{}", checksummed)
+    }
+
+    /// Parses a hex address with or without `0x`, validating the EIP-55 checksum, or the
+    /// EIP-1191 checksum of `chain_id` when one is given, if the input is mixed-case
+    pub fn parse(s: &str, chain_id: Option<u64>) -> Result<Address, Error> {
+        let hex = if s.starts_with("0x") || s.starts_with("0X") { &s[2..] } else { s };
+        if hex.len() != 40 {
+            return Err(Error::InvalidAddress(s.to_string()));
+        }
+        let bytes: Vec<u8> = hex.from_hex().map_err(|_| Error::InvalidAddress(s.to_string()))?;
+        let address = Address(H160::from(&bytes[..]));
+        let mixed_case = hex.chars().any(|c| c.is_ascii_lowercase()) && hex.chars().any(|c| c.is_ascii_uppercase());
+        if mixed_case && address.to_checksum(chain_id)[2..] != *hex {
+            return Err(Error::InvalidAddressChecksum(s.to_string()));
+        }
+        Ok(address)
+    }
+}
+
+/// EIP-1191 prefixes the hashed address with the chain id
+fn checksum_preimage(hex: &str, chain_id: Option<u64>) -> String {
+    match chain_id {
+        Some(chain_id) => format!("{}0x{}", chain_id, hex),
+        None => hex.to_string()
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.to_checksum(None))
+    }
+}
+
+impl FromStr for Address {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Address, Error> {
+        Address::parse(s, None)
+    }
+}
+
+impl From<H160> for Address {
+    fn from(address: H160) -> Address {
+        Address(address)
+    }
+}
+
+impl From<Address> for H160 {
+    fn from(address: Address) -> H160 {
+        address.0
+    }
+}
+
+impl Serialize for Address {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_checksum(None))
+    }
+}
+
+impl<'de> Deserialize<'de> for Address {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Address, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(de::Error::custom)
+    }
+}
+
+/// Returns the address of the account controlled by `private_key`. Fails with
+/// `Error::InvalidPrivateKey` when the key is zero or not below the secp256k1 curve order,
+/// which no account can be controlled by.
+pub fn address_from_private_key(private_key: &H256) -> Result<H160, Error> {
+    let secp = Secp256k1::signing_only();
+    let secret_key = SecretKey::from_slice(&secp, &private_key.0)?;
+    Ok(address_from_public_key(&PublicKey::from_secret_key(&secp, &secret_key)))
+}
+
+/// Returns the address of an account, the last 20 bytes of the keccak hash of its
+/// uncompressed public key
+pub fn address_from_public_key(public_key: &PublicKey) -> H160 {
+    let hash = keccak256(&public_key.serialize_uncompressed()[1..]);
+    H160::from(&hash[12..])
+}
+
+/// Returns the address of a contract created with CREATE by `sender` with `nonce`, the
+/// last 20 bytes of `keccak256(rlp([sender, nonce]))`
+pub fn create_address(sender: &H160, nonce: &U256) -> H160 {
+    let mut stream = RlpStream::new_list(2);
+    stream.append(sender);
+    stream.append(nonce);
+    H160::from(&keccak256(&stream.out())[12..])
+}
+
+/// Returns the address of a contract created with CREATE2 by `deployer`, the last 20 bytes
+/// of `keccak256(0xff ++ deployer ++ salt ++ init_code_hash)` as per EIP-1014
+pub fn create2_address(deployer: &H160, salt: &H256, init_code_hash: &H256) -> H160 {
+    let mut preimage = Vec::with_capacity(85);
+    preimage.push(0xff);
+    preimage.extend_from_slice(&deployer[..]);
+    preimage.extend_from_slice(&salt[..]);
+    preimage.extend_from_slice(&init_code_hash[..]);
+    H160::from(&keccak256(&preimage)[12..])
+}
+
+mod test {
+
+    #[test]
+    fn test_address_from_private_key() {
+        use ethereum_types::*;
+        use address::address_from_private_key;
+
+        assert_eq!(
+            H160::from("0x9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f"),
+            address_from_private_key(&H256::from("0x4646464646464646464646464646464646464646464646464646464646464646")).unwrap()
+        );
+        assert_eq!(
+            H160::from("0x2c7536e3605d9c16a7a3d7b1898e529396a65c23"),
+            address_from_private_key(&H256::from("0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318")).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_address_from_private_key_rejects_invalid_key() {
+        use ethereum_types::*;
+        use address::address_from_private_key;
+        use error::Error;
+
+        assert_eq!(Err(Error::InvalidPrivateKey), address_from_private_key(&H256::zero()));
+    }
+
+    #[test]
+    fn test_address_from_public_key() {
+        use ethereum_types::*;
+        use rustc_hex::FromHex;
+        use secp256k1::key::PublicKey;
+        use secp256k1::Secp256k1;
+        use address::address_from_public_key;
+
+        let secp = Secp256k1::without_caps();
+        let bytes: Vec<u8> = "044bc2a31265153f07e70e0bab08724e6b85e217f8cd628ceb62974247bb493382ce28cab79ad7119ee1ad3ebcdb98a16805211530ecc6cfefa1b88e6dff99232a"
+            .from_hex().unwrap();
+        let public_key = PublicKey::from_slice(&secp, &bytes).unwrap();
+        assert_eq!(
+            H160::from("0x9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f"),
+            address_from_public_key(&public_key)
+        );
+    }
+
+    #[test]
+    fn test_create_address() {
+        use ethereum_types::*;
+        use address::create_address;
+
+        let sender = H160::from("0x6ac7ea33f8831ea9dcc53393aaa88b25a785dbf0");
+        assert_eq!(H160::from("0xcd234a471b72ba2f1ccf0a70fcaba648a5eecd8d"), create_address(&sender, &U256::zero()));
+        assert_eq!(H160::from("0x343c43a37d37dff08ae8c4a11544c718abb4fcf8"), create_address(&sender, &U256::one()));
+        assert_eq!(H160::from("0xf778b86fa74e846c4f0a1fbd1335fe81c00a0c91"), create_address(&sender, &U256::from(2)));
+        assert_eq!(H160::from("0x08e190dcb7b73f5fcdabb43e102215c83659a76d"), create_address(&sender, &U256::from(0x80)));
+        assert_eq!(H160::from("0x190d1182a337644a231fa9dc8a1b45993b6af594"), create_address(&sender, &U256::from(0x1234567)));
+    }
+
+    #[test]
+    fn test_create2_address() {
+        use ethereum_types::*;
+        use tiny_keccak::keccak256;
+        use address::create2_address;
+
+        // examples from EIP-1014
+        assert_eq!(
+            H160::from("0x4d1a2e2bb4f88f0250f26ffff098b0b30b26bf38"),
+            create2_address(&H160::zero(), &H256::zero(), &H256::from(keccak256(&[0x00])))
+        );
+        assert_eq!(
+            H160::from("0xb928f69bb1d91cd65274e3c79d8986362984fda3"),
+            create2_address(&H160::from("0xdeadbeef00000000000000000000000000000000"), &H256::zero(), &H256::from(keccak256(&[0x00])))
+        );
+        assert_eq!(
+            H160::from("0x1d8bfdc5d46dc4f61d6b6115972536ebe6a8854c"),
+            create2_address(
+                &H160::from("0x00000000000000000000000000000000deadbeef"),
+                &H256::from("0x00000000000000000000000000000000000000000000000000000000cafebabe"),
+                &H256::from(keccak256(&[0xde, 0xad, 0xbe, 0xef].repeat(11)))
+            )
+        );
+        assert_eq!(
+            H160::from("0xe33c0c7f7df4809055c3eba6c09cfe4baf1bd9e0"),
+            create2_address(&H160::zero(), &H256::zero(), &H256::from(keccak256(&[])))
+        );
+    }
+
+    #[test]
+    fn test_formats_checksums() {
+        use address::Address;
+
+        // examples from EIP-55 and EIP-1191
+        let cases = [
+            ("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed", "0x5aaEB6053f3e94c9b9a09f33669435E7ef1bEAeD", "0x5aAeb6053F3e94c9b9A09F33669435E7EF1BEaEd"),
+            ("0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359", "0xFb6916095cA1Df60bb79ce92cE3EA74c37c5d359", "0xFb6916095CA1dF60bb79CE92ce3Ea74C37c5D359"),
+            ("0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB", "0xDBF03B407c01E7CD3cBea99509D93F8Dddc8C6FB", "0xdbF03B407C01E7cd3cbEa99509D93f8dDDc8C6fB"),
+            ("0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb", "0xD1220A0Cf47c7B9BE7a2e6ba89F429762E7B9adB", "0xd1220a0CF47c7B9Be7A2E6Ba89f429762E7b9adB")
+        ];
+        for &(eip55, rsk_mainnet, rsk_testnet) in cases.iter() {
+            let address: Address = eip55.to_lowercase().parse().unwrap();
+            assert_eq!(eip55, address.to_string());
+            assert_eq!(eip55, address.to_checksum(None));
+            assert_eq!(rsk_mainnet, address.to_checksum(Some(30)));
+            assert_eq!(rsk_testnet, address.to_checksum(Some(31)));
+        }
+    }
+
+    #[test]
+    fn test_parses_checksums() {
+        use ethereum_types::*;
+        use address::Address;
+        use error::Error;
+
+        let expected = Address(H160::from("0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed"));
+        assert_eq!(Ok(expected), "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".parse());
+        assert_eq!(Ok(expected), "5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".parse());
+        assert_eq!(Ok(expected), "0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed".parse());
+        assert_eq!(Ok(expected), "0x5AAEB6053F3E94C9B9A09F33669435E7EF1BEAED".parse());
+        assert_eq!(Ok(expected), Address::parse("0x5aaEB6053f3e94c9b9a09f33669435E7ef1bEAeD", Some(30)));
+
+        assert_eq!(
+            Err(Error::InvalidAddressChecksum("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeD".to_string())),
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeD".parse::<Address>()
+        );
+        assert!(Address::parse("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed", Some(30)).is_err());
+        assert!("0x5aaeb6053f3e94c9b9a09f33669435e7ef1bea".parse::<Address>().is_err());
+        assert!("0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaeg".parse::<Address>().is_err());
+    }
+
+    #[test]
+    fn test_address_serde() {
+        use ethereum_types::*;
+        use serde_json;
+        use address::Address;
+
+        let address = Address(H160::from("0xfb6916095ca1df60bb79ce92ce3ea74c37c5d359"));
+        let json = serde_json::to_string(&address).unwrap();
+        assert_eq!("\"0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359\"", json);
+        assert_eq!(address, serde_json::from_str(&json).unwrap());
+        assert!(serde_json::from_str::<Address>("\"0xFB6916095ca1df60bB79Ce92cE3Ea74c37c5d359\"").is_err());
+    }
+}
@@ -0,0 +1,258 @@
+use std::future::{self, Future};
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+use ethereum_types::{H160, H256};
+use error::Error;
+use signature::Signature;
+use signed_transaction::SignedTransaction;
+use signer::{check_transaction, finish_transaction, LocalSigner, Signer};
+use typed_transaction::TypedTransaction;
+
+/// Future returned by an `AsyncSigner`
+pub type SignFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T, Error>> + Send + 'a>>;
+
+/// Asynchronous counterpart of `Signer` for backends that sit behind a network hop. The
+/// returned futures do not depend on any particular executor.
+///
+/// `LocalSigner` futures complete immediately. `RemoteSigner` futures make the HTTP request
+/// on a background thread, so awaiting them never blocks the executor. Other `Signer`
+/// implementations that block should follow `RemoteSigner` and use `spawn_blocking`
+/// rather than be wrapped in a ready future.
+pub trait AsyncSigner {
+    /// Address of the account the signer signs for
+    fn address(&self) -> H160;
+
+    /// Chain the signer is bound to, if any
+    fn chain_id(&self) -> Option<u64> {
+        None
+    }
+
+    /// Signs a 32 byte hash
+    fn sign_hash<'a>(&'a self, hash: &H256) -> SignFuture<'a, Signature>;
+
+    /// Signs a transaction and returns it together with its encoding, hash and signature
+    fn sign_transaction<'a>(&'a self, tx: &TypedTransaction) -> SignFuture<'a, SignedTransaction> {
+        if let Err(e) = check_transaction(self.chain_id(), tx) {
+            return Box::pin(future::ready(Err(e)));
+        }
+        Box::pin(SignTransaction {
+            signature: self.sign_hash(&tx.signing_hash()),
+            address: self.address(),
+            transaction: tx.clone()
+        })
+    }
+}
+
+impl AsyncSigner for LocalSigner {
+    fn address(&self) -> H160 {
+        Signer::address(self)
+    }
+
+    fn chain_id(&self) -> Option<u64> {
+        Signer::chain_id(self)
+    }
+
+    fn sign_hash<'a>(&'a self, hash: &H256) -> SignFuture<'a, Signature> {
+        Box::pin(future::ready(Signer::sign_hash(self, hash)))
+    }
+
+    fn sign_transaction<'a>(&'a self, tx: &TypedTransaction) -> SignFuture<'a, SignedTransaction> {
+        Box::pin(future::ready(Signer::sign_transaction(self, tx)))
+    }
+}
+
+/// Runs blocking work such as a network request on its own thread and returns a future
+/// that completes with its result
+pub fn spawn_blocking<T, F>(work: F) -> SignFuture<'static, T>
+    where T: Send + 'static, F: FnOnce() -> Result<T, Error> + Send + 'static {
+    let shared = Arc::new(Mutex::new(Blocking { result: None, waker: None }));
+    let worker = shared.clone();
+    let spawned = thread::Builder::new().spawn(move || {
+        let result = panic::catch_unwind(AssertUnwindSafe(work))
+            .unwrap_or_else(|_| Err(Error::Remote("signing thread panicked".to_string())));
+        let mut state = worker.lock().unwrap();
+        state.result = Some(result);
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    });
+    match spawned {
+        Ok(_) => Box::pin(BlockingFuture(shared)),
+        Err(e) => Box::pin(future::ready(Err(Error::Remote(format!("cannot spawn signing thread: {}", e)))))
+    }
+}
+
+/// Result of blocking work and the task waiting for it
+struct Blocking<T> {
+    result: Option<Result<T, Error>>,
+    waker: Option<Waker>
+}
+
+/// Waits for blocking work running on another thread
+struct BlockingFuture<T>(Arc<Mutex<Blocking<T>>>);
+
+impl<T> Future for BlockingFuture<T> {
+    type Output = Result<T, Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let mut state = self.0.lock().unwrap();
+        match state.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Waits for a signature and encodes the transaction with it
+struct SignTransaction<'a> {
+    signature: SignFuture<'a, Signature>,
+    address: H160,
+    transaction: TypedTransaction
+}
+
+impl<'a> Future for SignTransaction<'a> {
+    type Output = Result<SignedTransaction, Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        match self.signature.as_mut().poll(cx) {
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(Err(e)) => Poll::Ready(Err(e)),
+            Poll::Ready(Ok(sig)) => Poll::Ready(finish_transaction(self.address, &self.transaction, &sig))
+        }
+    }
+}
+
+mod test {
+
+    #[test]
+    fn test_async_signer_matches_private_key_signing() {
+        use std::io::Read;
+        use std::fs::File;
+        use std::future::Future;
+        use std::pin::Pin;
+        use std::sync::Arc;
+        use std::task::{Context, Poll, Wake};
+        use std::thread::{self, Thread};
+        use ethereum_types::*;
+        use async_signer::{AsyncSigner, SignFuture};
+        use error::Error;
+        use raw_transaction::RawTransaction;
+        use signature::Signature;
+        use signer::{LocalSigner, Signer};
+        use serde_json;
+
+        #[derive(Deserialize)]
+        struct Signing {
+            signed: Vec<u8>,
+            private_key: H256
+        }
+
+        struct ThreadWaker(Thread);
+
+        impl Wake for ThreadWaker {
+            fn wake(self: Arc<Self>) {
+                self.0.unpark();
+            }
+        }
+
+        // minimal executor so the test does not depend on an async runtime
+        fn block_on<F: Future>(future: F) -> F::Output {
+            let mut future = Box::pin(future);
+            let waker = Arc::new(ThreadWaker(thread::current())).into();
+            let mut cx = Context::from_waker(&waker);
+            loop {
+                match future.as_mut().poll(&mut cx) {
+                    Poll::Ready(output) => return output,
+                    Poll::Pending => thread::park()
+                }
+            }
+        }
+
+        // completes on the second poll, like a response arriving from another task
+        struct Delayed<T>(Option<T>, bool);
+
+        impl<T: Unpin> Future for Delayed<T> {
+            type Output = T;
+
+            fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<T> {
+                if self.1 {
+                    Poll::Ready(self.0.take().unwrap())
+                } else {
+                    self.1 = true;
+                    cx.waker().wake_by_ref();
+                    Poll::Pending
+                }
+            }
+        }
+
+        // in-process stand-in for a remote signing service, using the default
+        // sign_transaction of AsyncSigner
+        struct MockSigner {
+            key: LocalSigner,
+            chain_id: Option<u64>
+        }
+
+        impl AsyncSigner for MockSigner {
+            fn address(&self) -> H160 {
+                Signer::address(&self.key)
+            }
+
+            fn chain_id(&self) -> Option<u64> {
+                self.chain_id
+            }
+
+            fn sign_hash<'a>(&'a self, hash: &H256) -> SignFuture<'a, Signature> {
+                Box::pin(Delayed(Some(Signer::sign_hash(&self.key, hash)), false))
+            }
+        }
+
+        let mut file = File::open("./test/test_txs.json").unwrap();
+        let mut f_string = String::new();
+        file.read_to_string(&mut f_string).unwrap();
+        let txs: Vec<(RawTransaction, Signing)> = serde_json::from_str(&f_string).unwrap();
+        for (tx, signed) in txs.into_iter() {
+            let signer = MockSigner { key: LocalSigner::new(&signed.private_key).unwrap(), chain_id: None };
+            let signed_tx = block_on(tx.sign_async(&signer, &1)).unwrap();
+            assert_eq!(signed.signed, signed_tx.raw_bytes);
+            assert_eq!(AsyncSigner::address(&signer), signed_tx.sender);
+        }
+
+        let signer = MockSigner { key: LocalSigner::new(&H256::from(0x46)).unwrap(), chain_id: Some(5) };
+        assert_eq!(
+            Err(Error::ChainIdMismatch { expected: 5, found: Some(1) }),
+            block_on(RawTransaction::default().sign_async(&signer, &1))
+        );
+    }
+
+    #[test]
+    fn test_local_signer_completes_immediately() {
+        use std::sync::Arc;
+        use std::task::{Context, Poll, Wake};
+        use ethereum_types::*;
+        use async_signer::AsyncSigner;
+        use eip1559_transaction::Eip1559Transaction;
+        use signer::LocalSigner;
+
+        struct NoopWaker;
+
+        impl Wake for NoopWaker {
+            fn wake(self: Arc<Self>) {}
+        }
+
+        let signer = LocalSigner::new(&H256::from(0x46)).unwrap();
+        let tx = Eip1559Transaction { chain_id: 1, ..Default::default() };
+        let waker = Arc::new(NoopWaker).into();
+        let mut cx = Context::from_waker(&waker);
+        let mut future = AsyncSigner::sign_transaction(&signer, &tx.clone().into());
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(signed) => assert_eq!(tx.sign(&H256::from(0x46)).unwrap(), signed.unwrap().raw_bytes),
+            Poll::Pending => panic!("local signing should not wait")
+        }
+    }
+}
@@ -0,0 +1,340 @@
+use ethereum_types::{H160, H256, U256};
+use c_kzg::{ethereum_kzg_settings, Blob, Bytes48, KzgProof};
+use rlp::RlpStream;
+use access_list::AccessList;
+use error::Error;
+use sha2::{Digest, Sha256};
+use signature::ecdsa_sign;
+use typed_transaction::{envelope, TypedEnvelope};
+
+/// EIP-2718 type byte of a blob transaction
+pub const TRANSACTION_TYPE: u8 = 0x03;
+/// Version byte of a versioned hash derived from a KZG commitment
+const VERSIONED_HASH_VERSION_KZG: u8 = 0x01;
+/// Size of a single blob in bytes
+pub const BYTES_PER_BLOB: usize = 131072;
+/// Size of a compressed KZG commitment or proof in bytes
+pub const BYTES_PER_COMMITMENT: usize = 48;
+/// Most blobs a single transaction may carry: the Cancun per-block maximum, which is also
+/// the per-transaction limit since Osaka
+pub const MAX_BLOBS_PER_TRANSACTION: usize = 6;
+
+/// Description of an EIP-4844 blob transaction
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+pub struct BlobTransaction {
+    /// Chain the transaction is valid on
+    #[serde(rename = "chainId")]
+    pub chain_id: u64,
+    /// Nonce
+    pub nonce: U256,
+    /// Maximum fee per gas paid to the block producer
+    #[serde(rename = "maxPriorityFeePerGas")]
+    pub max_priority_fee_per_gas: U256,
+    /// Maximum total fee per gas, including the base fee
+    #[serde(rename = "maxFeePerGas")]
+    pub max_fee_per_gas: U256,
+    /// Gas amount
+    pub gas: U256,
+    /// Recipient (blob transactions cannot create contracts)
+    pub to: H160,
+    /// Transfered value
+    pub value: U256,
+    /// Input data
+    pub data: Vec<u8>,
+    /// Addresses and storage keys the transaction plans to access
+    #[serde(rename = "accessList", default)]
+    pub access_list: AccessList,
+    /// Maximum fee per unit of blob gas
+    #[serde(rename = "maxFeePerBlobGas")]
+    pub max_fee_per_blob_gas: U256,
+    /// Versioned hashes of the KZG commitments to the blobs
+    #[serde(rename = "blobVersionedHashes")]
+    pub blob_versioned_hashes: Vec<H256>,
+    /// Blobs, commitments and proofs; only needed to gossip the transaction
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sidecar: Option<BlobSidecar>
+}
+
+/// Blobs together with their KZG commitments and proofs
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+pub struct BlobSidecar {
+    /// Blob data, `BYTES_PER_BLOB` bytes each
+    pub blobs: Vec<Vec<u8>>,
+    /// KZG commitment to each blob
+    pub commitments: Vec<Vec<u8>>,
+    /// KZG proof for each blob
+    pub proofs: Vec<Vec<u8>>
+}
+
+impl BlobTransaction {
+    /// Signs and returns the canonical envelope `0x03 || rlp([...])`, which is what the
+    /// transaction hash is computed over
+    pub fn sign(&self, private_key: &H256) -> Result<Vec<u8>, Error> {
+        self.validate()?;
+        TypedEnvelope::sign(self, private_key)
+    }
+
+    /// Signs and returns the network envelope `0x03 || rlp([tx, blobs, commitments, proofs])`
+    /// used to gossip the transaction together with its sidecar
+    pub fn sign_pooled(&self, private_key: &H256) -> Result<Vec<u8>, Error> {
+        self.validate_sidecar()?;
+        let sig = ecdsa_sign(&self.signing_hash(), &private_key.0)?;
+        self.encode_pooled(sig.recovery_id as u64, &sig.r.into(), &sig.s.into())
+    }
+
+    /// Returns the network envelope carrying the given signature and the sidecar
+    pub(crate) fn encode_pooled(&self, y_parity: u64, r: &U256, s: &U256) -> Result<Vec<u8>, Error> {
+        let sidecar = self.sidecar.as_ref().ok_or(Error::MissingBlobSidecar)?;
+        let mut tx = RlpStream::new();
+        tx.begin_list(4);
+        self.append_signed(&mut tx, y_parity, r, s);
+        tx.append_list::<Vec<u8>, _>(&sidecar.blobs);
+        tx.append_list::<Vec<u8>, _>(&sidecar.commitments);
+        tx.append_list::<Vec<u8>, _>(&sidecar.proofs);
+        Ok(envelope(TRANSACTION_TYPE, tx))
+    }
+
+    /// Checks the number of blobs, and the sidecar if there is one
+    pub(crate) fn validate(&self) -> Result<(), Error> {
+        match self.sidecar {
+            Some(_) => self.validate_sidecar(),
+            None => check_blob_count(self.blob_versioned_hashes.len())
+        }
+    }
+
+    /// Checks that the transaction carries between one and `MAX_BLOBS_PER_TRANSACTION`
+    /// blobs, that the sidecar is well formed, that `blob_versioned_hashes` match its
+    /// commitments and that each proof shows its commitment is to the blob, using the
+    /// mainnet trusted setup
+    pub fn validate_sidecar(&self) -> Result<(), Error> {
+        let sidecar = self.sidecar.as_ref().ok_or(Error::MissingBlobSidecar)?;
+        let count = self.blob_versioned_hashes.len();
+        check_blob_count(count)?;
+        if sidecar.blobs.len() != count
+            || sidecar.commitments.len() != count
+            || sidecar.proofs.len() != count
+            || sidecar.blobs.iter().any(|b| b.len() != BYTES_PER_BLOB)
+            || sidecar.commitments.iter().any(|c| c.len() != BYTES_PER_COMMITMENT)
+            || sidecar.proofs.iter().any(|p| p.len() != BYTES_PER_COMMITMENT) {
+            return Err(Error::InvalidBlobSidecar);
+        }
+        for (i, (hash, commitment)) in self.blob_versioned_hashes.iter()
+            .zip(sidecar.commitments.iter())
+            .enumerate() {
+            if *hash != kzg_to_versioned_hash(commitment) {
+                return Err(Error::BlobVersionedHashMismatch(i));
+            }
+        }
+        verify_blob_proofs(sidecar)
+    }
+
+    /// Returns the unsigned typed transaction envelope, which is what the signature is made over
+    pub fn encode_unsigned(&self) -> Vec<u8> {
+        TypedEnvelope::encode_unsigned(self)
+    }
+
+    /// Returns the hash the transaction signature is made over
+    pub fn signing_hash(&self) -> H256 {
+        TypedEnvelope::signing_hash(self)
+    }
+}
+
+impl TypedEnvelope for BlobTransaction {
+    const TRANSACTION_TYPE: u8 = TRANSACTION_TYPE;
+
+    fn append_fields(&self, s: &mut RlpStream) {
+        s.append(&self.chain_id);
+        s.append(&self.nonce);
+        s.append(&self.max_priority_fee_per_gas);
+        s.append(&self.max_fee_per_gas);
+        s.append(&self.gas);
+        s.append(&self.to);
+        s.append(&self.value);
+        s.append(&self.data);
+        s.append(&self.access_list);
+        s.append(&self.max_fee_per_blob_gas);
+        s.append_list(&self.blob_versioned_hashes);
+    }
+}
+
+fn check_blob_count(count: usize) -> Result<(), Error> {
+    if count == 0 || count > MAX_BLOBS_PER_TRANSACTION {
+        return Err(Error::InvalidBlobCount(count));
+    }
+    Ok(())
+}
+
+fn verify_blob_proofs(sidecar: &BlobSidecar) -> Result<(), Error> {
+    let settings = ethereum_kzg_settings();
+    for (i, ((blob, commitment), proof)) in sidecar.blobs.iter()
+        .zip(sidecar.commitments.iter())
+        .zip(sidecar.proofs.iter())
+        .enumerate() {
+        let blob = Blob::from_bytes(blob).map_err(|_| Error::InvalidBlobSidecar)?;
+        let commitment = Bytes48::from_bytes(commitment).map_err(|_| Error::InvalidBlobSidecar)?;
+        let proof = Bytes48::from_bytes(proof).map_err(|_| Error::InvalidBlobSidecar)?;
+        // points that do not decode fail verification just like a wrong proof
+        if !KzgProof::verify_blob_kzg_proof(&blob, &commitment, &proof, settings).unwrap_or(false) {
+            return Err(Error::InvalidBlobProof(i));
+        }
+    }
+    Ok(())
+}
+
+/// Computes the versioned hash `0x01 || sha256(commitment)[1..]` of a KZG commitment
+pub fn kzg_to_versioned_hash(commitment: &[u8]) -> H256 {
+    let mut hash = H256::from(&Sha256::digest(commitment)[..]);
+    hash.0[0] = VERSIONED_HASH_VERSION_KZG;
+    hash
+}
+
+mod test {
+
+    #[test]
+    fn test_matches_published_transactions() {
+        use std::io::Read;
+        use std::fs::File;
+        use ethereum_types::*;
+        use rustc_hex::FromHex;
+        use address::address_from_private_key;
+        use blob_transaction::BlobTransaction;
+        use signed_transaction::SignedTransaction;
+        use typed_transaction::TypedTransaction;
+        use serde_json;
+
+        // transactions published with the test suites of alloy-consensus 1.0.41 and
+        // alloy-rpc-types-eth 1.0.41 and 0.9.2, given either raw or as (y parity, r, s)
+        #[derive(Deserialize)]
+        struct Published {
+            description: String,
+            raw: Option<String>,
+            signature: Option<(u64, U256, U256)>,
+            hash: Option<H256>,
+            sender: Option<H160>,
+            signing_hash: Option<H256>
+        }
+
+        let mut file = File::open("./test/test_txs_eip4844.json").unwrap();
+        let mut f_string = String::new();
+        file.read_to_string(&mut f_string).unwrap();
+        let txs: Vec<(BlobTransaction, Published)> = serde_json::from_str(&f_string).unwrap();
+        for (tx, published) in txs.into_iter() {
+            let description = published.description;
+            if let Some(signing_hash) = published.signing_hash {
+                assert_eq!(signing_hash, tx.signing_hash(), "{}", description);
+            }
+            let tx = TypedTransaction::from(tx);
+            let raw = match (published.raw, published.signature) {
+                (Some(raw), _) => raw[2..].from_hex().unwrap(),
+                (None, Some((v, r, s))) => tx.encode_signed(v, &r, &s),
+                (None, None) => panic!("{} carries no signature", description)
+            };
+            let decoded = SignedTransaction::decode(&raw).unwrap();
+            assert_eq!(tx, decoded.transaction, "{}", description);
+            assert_eq!(raw, decoded.encode(), "{}", description);
+            if let Some(hash) = published.hash {
+                assert_eq!(hash, decoded.hash, "{}", description);
+            }
+            if let Some(sender) = published.sender {
+                assert_eq!(sender, decoded.sender, "{}", description);
+            }
+
+            let private_key = H256::from(0x46);
+            let signed = tx.sign_transaction(&private_key).unwrap();
+            assert_eq!(address_from_private_key(&private_key).unwrap(), signed.sender);
+            assert_eq!(signed, SignedTransaction::decode(&tx.sign(&private_key).unwrap()).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_versioned_hash_of_empty_blob_commitment() {
+        use ethereum_types::*;
+        use blob_transaction::kzg_to_versioned_hash;
+
+        // the commitment to an all-zero blob is the compressed point at infinity
+        let mut commitment = vec![0u8; 48];
+        commitment[0] = 0xc0;
+        assert_eq!(
+            H256::from("0x010657f37554c781402a22917dee2f75def7ab966d7b770905398eba3c444014"),
+            kzg_to_versioned_hash(&commitment)
+        );
+    }
+
+    #[test]
+    fn test_signs_pooled_transaction() {
+        use ethereum_types::*;
+        use rlp::Rlp;
+        use blob_transaction::*;
+        use error::Error;
+
+        let mut infinity = vec![0u8; 48];
+        infinity[0] = 0xc0;
+        let mut tx = BlobTransaction {
+            chain_id: 1,
+            to: H160::from(0x35),
+            max_fee_per_blob_gas: U256::from(1),
+            blob_versioned_hashes: vec![kzg_to_versioned_hash(&infinity)],
+            ..Default::default()
+        };
+        let private_key = H256::from(0x46);
+        assert_eq!(Err(Error::MissingBlobSidecar), tx.sign_pooled(&private_key));
+
+        tx.sidecar = Some(BlobSidecar {
+            blobs: vec![vec![0u8; BYTES_PER_BLOB]],
+            commitments: vec![infinity.clone()],
+            proofs: vec![infinity.clone()]
+        });
+        let canonical = tx.sign(&private_key).unwrap();
+        let pooled = tx.sign_pooled(&private_key).unwrap();
+        assert_eq!(0x03, pooled[0]);
+        let wrapper = Rlp::new(&pooled[1..]);
+        assert_eq!(4, wrapper.item_count().unwrap());
+        assert_eq!(&canonical[1..], wrapper.at(0).unwrap().as_raw());
+        assert_eq!(vec![vec![0u8; BYTES_PER_BLOB]], wrapper.list_at::<Vec<u8>>(1).unwrap());
+        assert_eq!(vec![infinity.clone()], wrapper.list_at::<Vec<u8>>(2).unwrap());
+
+        // the proof only holds for the all-zero blob
+        tx.sidecar.as_mut().unwrap().blobs[0][31] = 1;
+        assert_eq!(Err(Error::InvalidBlobProof(0)), tx.sign_pooled(&private_key));
+        assert_eq!(Err(Error::InvalidBlobProof(0)), tx.sign(&private_key));
+        tx.sidecar.as_mut().unwrap().blobs[0][31] = 0;
+        tx.sidecar.as_mut().unwrap().proofs[0] = vec![0x11; 48];
+        assert_eq!(Err(Error::InvalidBlobProof(0)), tx.sign_pooled(&private_key));
+
+        tx.blob_versioned_hashes = vec![H256::from(1)];
+        assert_eq!(Err(Error::BlobVersionedHashMismatch(0)), tx.sign_pooled(&private_key));
+        tx.blob_versioned_hashes = vec![kzg_to_versioned_hash(&infinity); 2];
+        assert_eq!(Err(Error::InvalidBlobSidecar), tx.sign_pooled(&private_key));
+    }
+
+    #[test]
+    fn test_rejects_blob_counts_out_of_range() {
+        use ethereum_types::*;
+        use blob_transaction::*;
+        use error::Error;
+
+        let mut infinity = vec![0u8; 48];
+        infinity[0] = 0xc0;
+        let private_key = H256::from(0x46);
+        let mut tx = BlobTransaction { chain_id: 1, ..Default::default() };
+        assert_eq!(Err(Error::InvalidBlobCount(0)), tx.sign(&private_key));
+
+        tx.sidecar = Some(BlobSidecar::default());
+        assert_eq!(Err(Error::InvalidBlobCount(0)), tx.sign(&private_key));
+        assert_eq!(Err(Error::InvalidBlobCount(0)), tx.sign_pooled(&private_key));
+
+        let count = MAX_BLOBS_PER_TRANSACTION + 1;
+        tx.blob_versioned_hashes = vec![kzg_to_versioned_hash(&infinity); count];
+        tx.sidecar = Some(BlobSidecar {
+            blobs: vec![vec![0u8; BYTES_PER_BLOB]; count],
+            commitments: vec![infinity.clone(); count],
+            proofs: vec![infinity.clone(); count]
+        });
+        assert_eq!(Err(Error::InvalidBlobCount(count)), tx.sign_pooled(&private_key));
+        tx.sidecar = None;
+        assert_eq!(Err(Error::InvalidBlobCount(count)), tx.sign(&private_key));
+
+        tx.blob_versioned_hashes.truncate(MAX_BLOBS_PER_TRANSACTION);
+        assert!(tx.sign(&private_key).is_ok());
+    }
+}
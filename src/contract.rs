@@ -0,0 +1,254 @@
+use std::path::Path;
+use ethereum_types::{H160, U256};
+use abi::{FunctionSignature, Token};
+use address::create_address;
+use error::Error;
+use json_abi::{Abi, Function};
+use raw_transaction::RawTransaction;
+
+/// A deployed contract whose functions are called through its JSON ABI
+#[derive(Debug, Clone, PartialEq)]
+pub struct Contract {
+    address: H160,
+    abi: Abi
+}
+
+impl Contract {
+    /// Creates a contract at `address` with the given interface
+    pub fn new(address: H160, abi: Abi) -> Contract {
+        Contract { address, abi }
+    }
+
+    /// Creates a contract at `address` with the interface read from a JSON ABI file
+    pub fn load<P: AsRef<Path>>(address: H160, path: P) -> Result<Contract, Error> {
+        Ok(Contract::new(address, Abi::load(path)?))
+    }
+
+    /// Returns the address of the contract
+    pub fn address(&self) -> H160 {
+        self.address
+    }
+
+    /// Returns the interface of the contract
+    pub fn abi(&self) -> &Abi {
+        &self.abi
+    }
+
+    /// Resolves the function called with `args`. `name` is either a plain name, in which
+    /// case overloads are told apart by the arguments, or a signature such as
+    /// `transfer(address,uint)`, which is compared in its canonical form.
+    pub fn function(&self, name: &str, args: &[Token]) -> Result<&Function, Error> {
+        let candidates: Vec<&Function> = if name.contains('(') {
+            let signature = name.parse::<FunctionSignature>()?.signature();
+            self.abi.functions.iter().filter(|f| f.signature() == signature).collect()
+        } else {
+            self.abi.functions.iter().filter(|f| f.name == name).collect()
+        };
+        if candidates.is_empty() {
+            return Err(Error::InvalidAbi(format!("contract has no function {}", name)));
+        }
+
+        let mut matching = candidates.iter().filter(|f| type_check(f, args).is_ok());
+        match (matching.next(), matching.next()) {
+            (Some(function), None) => Ok(function),
+            (Some(_), Some(_)) => Err(Error::InvalidAbi(format!(
+                "call to {} is ambiguous between {}, call it by signature",
+                name, signatures(&candidates)
+            ))),
+            // a single candidate reports what is wrong with the arguments
+            (None, _) if candidates.len() == 1 => type_check(candidates[0], args).map(|_| candidates[0]),
+            (None, _) => Err(Error::InvalidAbi(format!(
+                "arguments match none of {}", signatures(&candidates)
+            )))
+        }
+    }
+
+    /// Encodes calldata calling the function `name` with `args`
+    pub fn encode_call(&self, name: &str, args: &[Token]) -> Result<Vec<u8>, Error> {
+        self.function(name, args)?.encode_input(args)
+    }
+
+    /// Returns a transaction to the contract calling the function `name` with `args`. The
+    /// nonce, gas, gas price and value are left for the caller to fill in.
+    pub fn call(&self, name: &str, args: &[Token]) -> Result<RawTransaction, Error> {
+        Ok(RawTransaction {
+            to: Some(self.address),
+            data: self.encode_call(name, args)?,
+            ..Default::default()
+        })
+    }
+}
+
+/// Creation code and interface of a contract to deploy
+#[derive(Debug, Clone, PartialEq)]
+pub struct Deployer {
+    abi: Abi,
+    bytecode: Vec<u8>
+}
+
+impl Deployer {
+    /// Creates a deployer for a contract with the given interface and creation bytecode
+    pub fn new(abi: Abi, bytecode: Vec<u8>) -> Deployer {
+        Deployer { abi, bytecode }
+    }
+
+    /// Returns the creation bytecode followed by the encoded constructor arguments
+    pub fn encode_constructor(&self, args: &[Token]) -> Result<Vec<u8>, Error> {
+        match self.abi.constructor {
+            Some(ref constructor) => constructor.encode_input(&self.bytecode, args),
+            None if args.is_empty() => Ok(self.bytecode.clone()),
+            None => Err(Error::InvalidAbi("contract has no constructor taking arguments".to_string()))
+        }
+    }
+
+    /// Returns the contract creation transaction sent by `sender` with `nonce`, together
+    /// with the contract it will create. The gas, gas price and value are left for the
+    /// caller to fill in.
+    pub fn deploy(&self, args: &[Token], sender: &H160, nonce: U256) -> Result<(RawTransaction, Contract), Error> {
+        let tx = RawTransaction {
+            nonce,
+            to: None,
+            data: self.encode_constructor(args)?,
+            ..Default::default()
+        };
+        Ok((tx, Contract::new(create_address(sender, &nonce), self.abi.clone())))
+    }
+}
+
+fn type_check(function: &Function, args: &[Token]) -> Result<(), Error> {
+    if function.inputs.len() != args.len() {
+        return Err(Error::InvalidAbi(format!(
+            "{} takes {} arguments, {} given", function.signature(), function.inputs.len(), args.len()
+        )));
+    }
+    function.inputs.iter().zip(args.iter()).try_for_each(|(param, arg)| param.kind.type_check(arg))
+}
+
+fn signatures(functions: &[&Function]) -> String {
+    functions.iter().map(|f| f.signature()).collect::<Vec<_>>().join(", ")
+}
+
+mod test {
+    #[test]
+    fn test_builds_calls() {
+        use ethereum_types::*;
+        use rustc_hex::FromHex;
+        use abi::Token;
+        use contract::Contract;
+
+        let address = H160::from(&[0x11; 20][..]);
+        let contract = Contract::load(address, "./test/test_abi_token.json").unwrap();
+        let to = Token::Address(H160::from(&[0x35; 20][..]));
+        let amount = Token::Uint(U256::from(1_000_000_000_000_000_000u64));
+
+        let tx = contract.call("transfer", &[to.clone(), amount.clone()]).unwrap();
+        assert_eq!(Some(address), tx.to);
+        assert_eq!(
+            "a9059cbb\
+             0000000000000000000000003535353535353535353535353535353535353535\
+             0000000000000000000000000000000000000000000000000de0b6b3a7640000".from_hex::<Vec<u8>>().unwrap(),
+            tx.data
+        );
+        assert_eq!(U256::zero(), tx.nonce);
+        assert_eq!(tx.data, contract.encode_call("transfer(address,uint)", &[to.clone(), amount.clone()]).unwrap());
+
+        // the overload taking a memo
+        let data = contract.encode_call("transfer", &[to.clone(), amount.clone(), Token::Bytes(vec![1])]).unwrap();
+        assert_eq!("transfer(address,uint256,bytes)", contract.abi().decode_input(&data).unwrap().0.signature());
+
+        let data = contract.encode_call("deposit", &[]).unwrap();
+        assert_eq!(contract.abi().functions[4].selector().to_vec(), data);
+    }
+
+    #[test]
+    fn test_checks_arguments() {
+        use ethereum_types::*;
+        use abi::Token;
+        use contract::Contract;
+
+        let contract = Contract::load(H160::zero(), "./test/test_abi_token.json").unwrap();
+        let to = Token::Address(H160::from(1));
+
+        assert!(contract.call("mint", &[]).is_err());
+        assert!(contract.call("balanceOf", &[]).is_err());
+        assert!(contract.call("balanceOf", &[Token::Uint(U256::one())]).is_err());
+        assert!(contract.call("transfer", &[to.clone(), Token::Int(U256::one())]).is_err());
+        assert!(contract.call("batchTransfer", &[Token::Array(vec![Token::Tuple(vec![to.clone()])])]).is_err());
+        assert!(contract.call("batchTransfer", &[Token::Array(vec![
+            Token::Tuple(vec![to.clone(), Token::Uint(U256::one())])
+        ])]).is_ok());
+    }
+
+    #[test]
+    fn test_resolves_overloads() {
+        use ethereum_types::*;
+        use abi::Token;
+        use contract::Contract;
+        use json_abi::Abi;
+
+        let abi = Abi::from_json(r#"[
+            {"type":"function","name":"set","inputs":[{"name":"v","type":"uint8"}],"outputs":[]},
+            {"type":"function","name":"set","inputs":[{"name":"v","type":"uint256"}],"outputs":[]},
+            {"type":"function","name":"set","inputs":[{"name":"v","type":"string"}],"outputs":[]},
+            {"type":"function","name":"foo","inputs":[{"name":"v","type":"uint256[]"}],"outputs":[]}
+        ]"#).unwrap();
+        let contract = Contract::new(H160::zero(), abi);
+
+        let function = contract.function("set", &[Token::Uint(U256::from(256))]).unwrap();
+        assert_eq!("set(uint256)", function.signature());
+        let function = contract.function("set", &[Token::String("x".to_string())]).unwrap();
+        assert_eq!("set(string)", function.signature());
+
+        // 1 fits both integer overloads
+        let one = [Token::Uint(U256::one())];
+        assert!(contract.function("set", &one).is_err());
+        assert_eq!("set(uint8)", contract.function("set(uint8)", &one).unwrap().signature());
+        assert!(contract.function("set(uint8)", &[Token::Uint(U256::from(256))]).is_err());
+        assert!(contract.function("set(bool)", &[Token::Bool(true)]).is_err());
+
+        // signatures are compared in canonical form
+        assert_eq!("set(uint256)", contract.function("set(uint)", &one).unwrap().signature());
+        assert_eq!("set(uint8)", contract.function(" set( uint8 )", &one).unwrap().signature());
+        let list = [Token::Array(vec![Token::Uint(U256::one())])];
+        assert_eq!("foo(uint256[])", contract.function("foo(uint[] )", &list).unwrap().signature());
+        assert!(contract.function("set(uint7)", &one).is_err());
+    }
+
+    #[test]
+    fn test_deploys() {
+        use ethereum_types::*;
+        use rustc_hex::FromHex;
+        use abi::Token;
+        use contract::Deployer;
+        use json_abi::Abi;
+
+        let abi = Abi::load("./test/test_abi_token.json").unwrap();
+        let deployer = Deployer::new(abi, vec![0x60, 0x80, 0x60, 0x40]);
+        let sender = H160::from("0x6ac7ea33f8831ea9dcc53393aaa88b25a785dbf0");
+        let args = [Token::String("Token".to_string()), Token::Uint(U256::from(1000))];
+
+        let (tx, contract) = deployer.deploy(&args, &sender, U256::one()).unwrap();
+        assert_eq!(None, tx.to);
+        assert_eq!(U256::one(), tx.nonce);
+        assert_eq!(
+            "60806040\
+             0000000000000000000000000000000000000000000000000000000000000040\
+             00000000000000000000000000000000000000000000000000000000000003e8\
+             0000000000000000000000000000000000000000000000000000000000000005\
+             546f6b656e000000000000000000000000000000000000000000000000000000".from_hex::<Vec<u8>>().unwrap(),
+            tx.data
+        );
+        assert_eq!(H160::from("0x343c43a37d37dff08ae8c4a11544c718abb4fcf8"), contract.address());
+        assert_eq!(deployer.abi, *contract.abi());
+
+        assert!(deployer.deploy(&args[..1], &sender, U256::zero()).is_err());
+        assert!(deployer.deploy(&[Token::Uint(U256::one()), Token::Uint(U256::one())], &sender, U256::zero()).is_err());
+
+        let bare = Deployer::new(Abi::from_json("[]").unwrap(), vec![0xfe]);
+        let (tx, contract) = bare.deploy(&[], &sender, U256::zero()).unwrap();
+        assert_eq!(vec![0xfe], tx.data);
+        assert_eq!(H160::from("0xcd234a471b72ba2f1ccf0a70fcaba648a5eecd8d"), contract.address());
+        assert!(bare.deploy(&args, &sender, U256::zero()).is_err());
+    }
+
+}
@@ -0,0 +1,131 @@
+use ethereum_types::{H160, H256, U256};
+use rlp::RlpStream;
+use access_list::AccessList;
+use error::Error;
+use typed_transaction::TypedEnvelope;
+
+/// EIP-2718 type byte of a dynamic-fee transaction
+pub const TRANSACTION_TYPE: u8 = 0x02;
+
+/// Description of an EIP-1559 dynamic-fee transaction
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Eip1559Transaction {
+    /// Chain the transaction is valid on
+    #[serde(rename = "chainId")]
+    pub chain_id: u64,
+    /// Nonce
+    pub nonce: U256,
+    /// Maximum fee per gas paid to the block producer
+    #[serde(rename = "maxPriorityFeePerGas")]
+    pub max_priority_fee_per_gas: U256,
+    /// Maximum total fee per gas, including the base fee
+    #[serde(rename = "maxFeePerGas")]
+    pub max_fee_per_gas: U256,
+    /// Gas amount
+    pub gas: U256,
+    /// Recipient (None when contract creation)
+    pub to: Option<H160>,
+    /// Transfered value
+    pub value: U256,
+    /// Input data
+    pub data: Vec<u8>,
+    /// Addresses and storage keys the transaction plans to access
+    #[serde(rename = "accessList", default)]
+    pub access_list: AccessList
+}
+
+impl Eip1559Transaction {
+    /// Signs and returns the typed transaction envelope `0x02 || rlp([...])`
+    pub fn sign(&self, private_key: &H256) -> Result<Vec<u8>, Error> {
+        TypedEnvelope::sign(self, private_key)
+    }
+
+    /// Returns the unsigned typed transaction envelope, which is what the signature is made over
+    pub fn encode_unsigned(&self) -> Vec<u8> {
+        TypedEnvelope::encode_unsigned(self)
+    }
+
+    /// Returns the hash the transaction signature is made over
+    pub fn signing_hash(&self) -> H256 {
+        TypedEnvelope::signing_hash(self)
+    }
+}
+
+impl TypedEnvelope for Eip1559Transaction {
+    const TRANSACTION_TYPE: u8 = TRANSACTION_TYPE;
+
+    fn append_fields(&self, s: &mut RlpStream) {
+        s.append(&self.chain_id);
+        s.append(&self.nonce);
+        s.append(&self.max_priority_fee_per_gas);
+        s.append(&self.max_fee_per_gas);
+        s.append(&self.gas);
+        if let Some(ref t) = self.to {
+            s.append(t);
+        } else {
+            s.append(&vec![]);
+        }
+        s.append(&self.value);
+        s.append(&self.data);
+        s.append(&self.access_list);
+    }
+}
+
+mod test {
+
+    #[test]
+    fn test_matches_published_transactions() {
+        use std::io::Read;
+        use std::fs::File;
+        use ethereum_types::*;
+        use rustc_hex::FromHex;
+        use address::address_from_private_key;
+        use eip1559_transaction::Eip1559Transaction;
+        use signed_transaction::SignedTransaction;
+        use typed_transaction::TypedTransaction;
+        use serde_json;
+
+        // transactions published with the test suites of alloy-consensus 1.0.41 and
+        // ethers-core 2.0.14, given either raw or as (y parity, r, s)
+        #[derive(Deserialize)]
+        struct Published {
+            description: String,
+            raw: Option<String>,
+            signature: Option<(u64, U256, U256)>,
+            hash: Option<H256>,
+            sender: Option<H160>,
+            signing_hash: Option<H256>
+        }
+
+        let mut file = File::open("./test/test_txs_eip1559.json").unwrap();
+        let mut f_string = String::new();
+        file.read_to_string(&mut f_string).unwrap();
+        let txs: Vec<(Eip1559Transaction, Published)> = serde_json::from_str(&f_string).unwrap();
+        for (tx, published) in txs.into_iter() {
+            let description = published.description;
+            if let Some(signing_hash) = published.signing_hash {
+                assert_eq!(signing_hash, tx.signing_hash(), "{}", description);
+            }
+            let tx = TypedTransaction::from(tx);
+            let raw = match (published.raw, published.signature) {
+                (Some(raw), _) => raw[2..].from_hex().unwrap(),
+                (None, Some((v, r, s))) => tx.encode_signed(v, &r, &s),
+                (None, None) => panic!("{} carries no signature", description)
+            };
+            let decoded = SignedTransaction::decode(&raw).unwrap();
+            assert_eq!(tx, decoded.transaction, "{}", description);
+            assert_eq!(raw, decoded.encode(), "{}", description);
+            if let Some(hash) = published.hash {
+                assert_eq!(hash, decoded.hash, "{}", description);
+            }
+            if let Some(sender) = published.sender {
+                assert_eq!(sender, decoded.sender, "{}", description);
+            }
+
+            let private_key = H256::from(0x46);
+            let signed = tx.sign_transaction(&private_key).unwrap();
+            assert_eq!(address_from_private_key(&private_key).unwrap(), signed.sender);
+            assert_eq!(signed, SignedTransaction::decode(&tx.sign(&private_key).unwrap()).unwrap());
+        }
+    }
+}
@@ -0,0 +1,490 @@
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use ethereum_types::{H256, U256};
+use rustc_hex::FromHex;
+use serde_json::Value;
+use error::Error;
+use signature::{ecdsa_sign, keccak256_hash};
+
+/// Name of the struct type describing the signing domain
+const DOMAIN_TYPE: &str = "EIP712Domain";
+/// Fields the domain may have, in the order EIP-712 lists them
+const DOMAIN_FIELDS: [(&str, &str); 5] = [
+    ("name", "string"),
+    ("version", "string"),
+    ("chainId", "uint256"),
+    ("verifyingContract", "address"),
+    ("salt", "bytes32")
+];
+
+/// EIP-712 typed structured data, in the JSON form taken by `eth_signTypedData_v4`
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct TypedData {
+    /// Struct type definitions, by name
+    pub types: BTreeMap<String, Vec<TypedDataField>>,
+    /// Type of `message`
+    #[serde(rename = "primaryType")]
+    pub primary_type: String,
+    /// Values of the `EIP712Domain` struct
+    pub domain: Value,
+    /// The struct being signed
+    pub message: Value
+}
+
+/// A member of a struct type
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct TypedDataField {
+    /// Member name
+    pub name: String,
+    /// Member type, such as `uint256`, `Person` or `Person[]`
+    #[serde(rename = "type")]
+    pub field_type: String
+}
+
+impl TypedData {
+    /// Parses the JSON `{types, primaryType, domain, message}` payload
+    pub fn from_json(json: &str) -> Result<TypedData, Error> {
+        ::serde_json::from_str(json).map_err(|e| Error::InvalidTypedData(e.to_string()))
+    }
+
+    /// Returns the encoding of a struct type followed by the types it references, e.g.
+    /// `Mail(Person from,Person to,string contents)Person(string name,address wallet)`
+    pub fn encode_type(&self, type_name: &str) -> Result<String, Error> {
+        let types = self.types_with_domain();
+        let mut deps = Vec::new();
+        collect_dependencies(&types, type_name, &mut deps)?;
+        deps.retain(|d| d != type_name);
+        deps.sort();
+        deps.insert(0, type_name.to_string());
+
+        let mut encoded = String::new();
+        for name in deps.iter() {
+            let fields: Vec<String> = types[name].iter()
+                .map(|f| format!("{} {}", f.field_type, f.name))
+                .collect();
+            encoded.push_str(&format!("{}({})", name, fields.join(",")));
+        }
+        Ok(encoded)
+    }
+
+    /// Returns keccak256 of `encode_type`
+    pub fn type_hash(&self, type_name: &str) -> Result<H256, Error> {
+        Ok(H256::from(&keccak256_hash(self.encode_type(type_name)?.as_bytes())[..]))
+    }
+
+    /// Returns `keccak256(typeHash || encodeData(data))` of a value of a struct type
+    pub fn hash_struct(&self, type_name: &str, data: &Value) -> Result<H256, Error> {
+        let types = self.types_with_domain();
+        self.hash_struct_with(&types, type_name, data)
+    }
+
+    /// Returns the hash of the domain
+    pub fn domain_separator(&self) -> Result<H256, Error> {
+        self.hash_struct(DOMAIN_TYPE, &self.domain)
+    }
+
+    /// Returns the digest that is signed, `keccak256(0x19 || 0x01 || domainSeparator ||
+    /// hashStruct(message))`. When the primary type is the domain itself the message hash
+    /// is left out.
+    pub fn signing_hash(&self) -> Result<H256, Error> {
+        let mut preimage = vec![0x19, 0x01];
+        preimage.extend_from_slice(&self.domain_separator()?);
+        if self.primary_type != DOMAIN_TYPE {
+            preimage.extend_from_slice(&self.hash_struct(&self.primary_type, &self.message)?);
+        }
+        Ok(H256::from(&keccak256_hash(&preimage)[..]))
+    }
+
+    /// Signs the typed data and returns the 65 byte `r || s || v` signature
+    pub fn sign(&self, private_key: &H256) -> Result<[u8; 65], Error> {
+        Ok(ecdsa_sign(&self.signing_hash()?, &private_key.0)?.to_bytes())
+    }
+
+    /// The declared types, plus an `EIP712Domain` inferred from the domain's fields if the
+    /// payload does not declare one
+    fn types_with_domain(&self) -> BTreeMap<String, Vec<TypedDataField>> {
+        let mut types = self.types.clone();
+        if !types.contains_key(DOMAIN_TYPE) {
+            let fields = DOMAIN_FIELDS.iter()
+                .filter(|&&(name, _)| self.domain.get(name).is_some_and(|v| !v.is_null()))
+                .map(|&(name, field_type)| TypedDataField {
+                    name: name.to_string(),
+                    field_type: field_type.to_string()
+                })
+                .collect();
+            types.insert(DOMAIN_TYPE.to_string(), fields);
+        }
+        types
+    }
+
+    fn hash_struct_with(&self, types: &BTreeMap<String, Vec<TypedDataField>>, type_name: &str, data: &Value)
+        -> Result<H256, Error> {
+        let fields = types.get(type_name)
+            .ok_or_else(|| Error::InvalidTypedData(format!("undefined type {}", type_name)))?;
+        let data = data.as_object()
+            .ok_or_else(|| Error::InvalidTypedData(format!("{} value must be an object", type_name)))?;
+        let mut encoded = self.type_hash(type_name)?.to_vec();
+        for field in fields.iter() {
+            let value = data.get(&field.name).unwrap_or(&Value::Null);
+            let word = self.encode_value(types, &field.field_type, value)
+                .map_err(|e| match e {
+                    Error::InvalidTypedData(e) => Error::InvalidTypedData(format!("{}.{}: {}", type_name, field.name, e)),
+                    e => e
+                })?;
+            encoded.extend_from_slice(&word);
+        }
+        Ok(H256::from(&keccak256_hash(&encoded)[..]))
+    }
+
+    /// Encodes a member value into its 32 byte word
+    fn encode_value(&self, types: &BTreeMap<String, Vec<TypedDataField>>, field_type: &str, value: &Value)
+        -> Result<H256, Error> {
+        if let Some((element_type, length)) = split_array(field_type)? {
+            let items = value.as_array().ok_or_else(|| invalid("expected an array"))?;
+            if length.is_some_and(|length| length != items.len()) {
+                return Err(invalid(&format!("expected {} elements", length.unwrap_or(0))));
+            }
+            let mut encoded = Vec::with_capacity(items.len() * 32);
+            for item in items.iter() {
+                encoded.extend_from_slice(&self.encode_value(types, element_type, item)?);
+            }
+            return Ok(H256::from(&keccak256_hash(&encoded)[..]));
+        }
+        if types.contains_key(field_type) {
+            // a missing struct is encoded as zero, as MetaMask does
+            return if value.is_null() {
+                Ok(H256::zero())
+            } else {
+                self.hash_struct_with(types, field_type, value)
+            };
+        }
+        if value.is_null() {
+            return Err(invalid("missing value"));
+        }
+        encode_atomic(field_type, value)
+    }
+}
+
+fn encode_atomic(field_type: &str, value: &Value) -> Result<H256, Error> {
+    match field_type {
+        "string" => {
+            let s = value.as_str().ok_or_else(|| invalid("expected a string"))?;
+            Ok(H256::from(&keccak256_hash(s.as_bytes())[..]))
+        }
+        "bytes" => {
+            let s = value.as_str().ok_or_else(|| invalid("expected a hex string"))?;
+            // like MetaMask, strings that are not hex are taken as UTF-8
+            let bytes = match s.strip_prefix("0x") {
+                Some(hex) => hex.from_hex().map_err(|_| invalid("invalid hex"))?,
+                None => s.as_bytes().to_vec()
+            };
+            Ok(H256::from(&keccak256_hash(&bytes)[..]))
+        }
+        "bool" => match *value {
+            Value::Bool(b) => Ok(H256::from(b as u64)),
+            _ => Err(invalid("expected a boolean"))
+        },
+        "address" => {
+            let bytes = parse_hex(value)?;
+            if bytes.len() != 20 {
+                return Err(invalid("expected a 20 byte address"));
+            }
+            let mut word = [0u8; 32];
+            word[12..].copy_from_slice(&bytes);
+            Ok(H256(word))
+        }
+        _ => {
+            if let Some(size) = field_type.strip_prefix("bytes") {
+                let size = parse_size(size, 1, 32, 1).ok_or_else(|| unknown_type(field_type))?;
+                let bytes = parse_hex(value)?;
+                if bytes.len() != size {
+                    return Err(invalid(&format!("expected {} bytes", size)));
+                }
+                let mut word = [0u8; 32];
+                word[..size].copy_from_slice(&bytes);
+                Ok(H256(word))
+            } else if let Some(bits) = field_type.strip_prefix("uint") {
+                let bits = parse_size(bits, 8, 256, 8).ok_or_else(|| unknown_type(field_type))?;
+                Ok(H256::from(parse_integer(value, false, bits)?))
+            } else if let Some(bits) = field_type.strip_prefix("int") {
+                let bits = parse_size(bits, 8, 256, 8).ok_or_else(|| unknown_type(field_type))?;
+                Ok(H256::from(parse_integer(value, true, bits)?))
+            } else {
+                Err(unknown_type(field_type))
+            }
+        }
+    }
+}
+
+/// Parses a JSON number or a decimal or `0x` hex string, optionally negative, into its
+/// 256 bit two's complement form, checking that it fits the type
+fn parse_integer(value: &Value, signed: bool, bits: usize) -> Result<U256, Error> {
+    let (negative, magnitude) = match *value {
+        Value::Number(ref n) => match (n.as_u64(), n.as_i64()) {
+            (Some(n), _) => (false, U256::from(n)),
+            (None, Some(n)) => (true, U256::from(n.unsigned_abs())),
+            _ => return Err(invalid("numbers beyond 64 bits must be given as strings"))
+        },
+        Value::String(ref s) => {
+            let (negative, digits) = match s.strip_prefix('-') {
+                Some(digits) => (true, digits),
+                None => (false, &s[..])
+            };
+            let magnitude = match digits.strip_prefix("0x") {
+                Some(hex) if !hex.is_empty() && hex.len() <= 64 => U256::from_str(hex).ok(),
+                Some(_) => None,
+                None if !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()) => {
+                    U256::from_dec_str(digits).ok()
+                }
+                None => None
+            };
+            (negative, magnitude.ok_or_else(|| invalid("invalid integer"))?)
+        }
+        _ => return Err(invalid("expected an integer"))
+    };
+
+    let in_range = if !signed {
+        !negative && magnitude.bits() <= bits
+    } else if negative {
+        // down to -2^(bits - 1)
+        magnitude.bits() < bits || (magnitude.bits() == bits && magnitude == U256::one() << (bits - 1))
+    } else {
+        magnitude.bits() < bits
+    };
+    if !in_range {
+        return Err(invalid("integer out of range"));
+    }
+    if negative && !magnitude.is_zero() {
+        Ok((!magnitude).overflowing_add(U256::one()).0)
+    } else {
+        Ok(magnitude)
+    }
+}
+
+fn parse_hex(value: &Value) -> Result<Vec<u8>, Error> {
+    value.as_str()
+        .and_then(|s| s.strip_prefix("0x"))
+        .and_then(|hex| hex.from_hex().ok())
+        .ok_or_else(|| invalid("expected a 0x prefixed hex string"))
+}
+
+/// Parses the size suffix of `bytesN`, `uintN` or `intN`
+fn parse_size(size: &str, min: usize, max: usize, step: usize) -> Option<usize> {
+    if size.is_empty() {
+        // `uint` and `int` are aliases of their 256 bit forms
+        return if step == 8 { Some(256) } else { None };
+    }
+    if size.starts_with('0') || !size.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    size.parse().ok().filter(|n| *n >= min && *n <= max && n % step == 0)
+}
+
+/// Splits `T[]` or `T[k]` into the element type and the fixed length, if any
+fn split_array(field_type: &str) -> Result<Option<(&str, Option<usize>)>, Error> {
+    if !field_type.ends_with(']') {
+        return Ok(None);
+    }
+    let open = field_type.rfind('[').ok_or_else(|| unknown_type(field_type))?;
+    let length = &field_type[open + 1..field_type.len() - 1];
+    let length = if length.is_empty() {
+        None
+    } else {
+        Some(length.parse().map_err(|_| unknown_type(field_type))?)
+    };
+    Ok(Some((&field_type[..open], length)))
+}
+
+/// Collects the struct types `type_name` references, including itself
+fn collect_dependencies(types: &BTreeMap<String, Vec<TypedDataField>>, type_name: &str, deps: &mut Vec<String>)
+    -> Result<(), Error> {
+    let mut base = type_name;
+    while let Some((element_type, _)) = split_array(base)? {
+        base = element_type;
+    }
+    if deps.iter().any(|d| d == base) {
+        return Ok(());
+    }
+    match types.get(base) {
+        Some(fields) => {
+            deps.push(base.to_string());
+            for field in fields.iter() {
+                collect_dependencies(types, &field.field_type, deps)?;
+            }
+            Ok(())
+        }
+        None if deps.is_empty() => Err(Error::InvalidTypedData(format!("undefined type {}", base))),
+        None => Ok(())
+    }
+}
+
+fn invalid(message: &str) -> Error {
+    Error::InvalidTypedData(message.to_string())
+}
+
+fn unknown_type(field_type: &str) -> Error {
+    Error::InvalidTypedData(format!("unknown type {}", field_type))
+}
+
+mod test {
+
+    #[test]
+    fn test_mail_example() {
+        use std::fs::File;
+        use std::io::Read;
+        use ethereum_types::*;
+        use rustc_hex::ToHex;
+        use eip712::TypedData;
+        use signature::keccak256_hash;
+        use signer::{LocalSigner, Signer};
+
+        let mut file = File::open("./test/test_eip712_mail.json").unwrap();
+        let mut json = String::new();
+        file.read_to_string(&mut json).unwrap();
+        let data = TypedData::from_json(&json).unwrap();
+
+        assert_eq!(
+            "Mail(Person from,Person to,string contents)Person(string name,address wallet)",
+            data.encode_type("Mail").unwrap()
+        );
+        assert_eq!(
+            H256::from("0xa0cedeb2dc280ba39b857546d74f5549c3a1d7bdc2dd96bf881f76108e23dac2"),
+            data.type_hash("Mail").unwrap()
+        );
+        assert_eq!(
+            H256::from("0xc52c0ee5d84264471806290a3f2c4cecfc5490626bf912d01f240d7a274b371e"),
+            data.hash_struct("Mail", &data.message).unwrap()
+        );
+        assert_eq!(
+            H256::from("0xf2cee375fa42b42143804025fc449deafd50cc031ca257e0b194a650a912090f"),
+            data.domain_separator().unwrap()
+        );
+        assert_eq!(
+            H256::from("0xbe609aee343fb3c4b28e1df9e632fca64fcfaede20f02e86244efddf30957bd2"),
+            data.signing_hash().unwrap()
+        );
+
+        let private_key = H256::from(&keccak256_hash(b"cow")[..]);
+        assert_eq!(
+            "4355c47d63924e8a72e509b65029052eb6c299d53a04e167c5775fd466751c9d\
+             07299936d304c153f6443dfa05f40ff007d72911b6f72307f996231605b915621c",
+            data.sign(&private_key).unwrap().to_hex::<String>()
+        );
+        let signer = LocalSigner::new(&private_key).unwrap();
+        assert_eq!(data.sign(&private_key).unwrap().to_vec(), signer.sign_typed_data(&data).unwrap().to_vec());
+    }
+
+    #[test]
+    fn test_metamask_vectors() {
+        use std::fs::File;
+        use std::io::Read;
+        use ethereum_types::*;
+        use serde_json;
+        use eip712::TypedData;
+
+        // signTypedData_v4 vectors of MetaMask's eth-sig-util (sign-typed-data.test.ts at
+        // dd8bd0e), as ported to the alloy-dyn-abi, ethers-core and eip-712 crates
+        #[derive(Deserialize)]
+        struct Vector {
+            description: String,
+            typed_data: TypedData,
+            signing_hash: H256
+        }
+
+        let mut file = File::open("./test/test_eip712_metamask.json").unwrap();
+        let mut json = String::new();
+        file.read_to_string(&mut json).unwrap();
+        let vectors: Vec<Vector> = serde_json::from_str(&json).unwrap();
+        for vector in vectors.into_iter() {
+            assert_eq!(vector.signing_hash, vector.typed_data.signing_hash().unwrap(), "{}", vector.description);
+        }
+    }
+
+    #[test]
+    fn test_arrays_and_atomic_types() {
+        use std::fs::File;
+        use std::io::Read;
+        use ethereum_types::*;
+        use eip712::TypedData;
+
+        let mut file = File::open("./test/test_eip712_arrays.json").unwrap();
+        let mut json = String::new();
+        file.read_to_string(&mut json).unwrap();
+        let data = TypedData::from_json(&json).unwrap();
+
+        // unreferenced types such as Group are left out
+        assert_eq!(
+            "Mail(Person from,Person[] to,string contents,bytes attachment,int32 nonce,bytes4 tag,\
+             bool[2] flags,uint128 amount,int256 delta)Person(string name,address[] wallets)",
+            data.encode_type("Mail").unwrap()
+        );
+        assert_eq!(
+            H256::from("0x2ab561b16d93df5bea986b330e030bbc84f7467540c6cdf8c3d0a2e655e792c9"),
+            data.signing_hash().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_infers_domain_type() {
+        use std::fs::File;
+        use std::io::Read;
+        use eip712::TypedData;
+
+        let mut file = File::open("./test/test_eip712_mail.json").unwrap();
+        let mut json = String::new();
+        file.read_to_string(&mut json).unwrap();
+        let data = TypedData::from_json(&json).unwrap();
+        let mut inferred = data.clone();
+        inferred.types.remove("EIP712Domain");
+        assert_eq!(data.domain_separator().unwrap(), inferred.domain_separator().unwrap());
+    }
+
+    #[test]
+    fn test_integer_ranges() {
+        use ethereum_types::*;
+        use serde_json::Value;
+        use eip712::encode_atomic;
+
+        let encode = |t: &str, v: &str| encode_atomic(t, &::serde_json::from_str::<Value>(v).unwrap());
+        assert_eq!(H256::from(255), encode("uint8", "255").unwrap());
+        assert!(encode("uint8", "256").is_err());
+        assert!(encode("uint8", "-1").is_err());
+        assert_eq!(H256::from(U256::max_value()), encode("int8", "-1").unwrap());
+        assert_eq!(H256::from(!U256::from(127)), encode("int8", "-128").unwrap());
+        assert!(encode("int8", "-129").is_err());
+        assert!(encode("int8", "128").is_err());
+        assert_eq!(H256::from(0x7f), encode("int8", "\"0x7f\"").unwrap());
+        assert_eq!(H256::from(U256::max_value()), encode("uint", &format!("\"{}\"", U256::max_value())).unwrap());
+        assert!(encode("uint7", "1").is_err());
+        assert!(encode("uint264", "1").is_err());
+        assert!(encode("bytes33", "\"0x00\"").is_err());
+        assert!(encode("bytes2", "\"0x00\"").is_err());
+        assert!(encode("address", "\"0x00\"").is_err());
+        assert!(encode("bool", "1").is_err());
+    }
+
+    #[test]
+    fn test_rejects_malformed_data() {
+        use eip712::TypedData;
+        use error::Error;
+
+        let json = r#"{
+            "types": {"Foo": [{"name": "a", "type": "uint256"}, {"name": "b", "type": "Bar[2]"}],
+                      "Bar": [{"name": "c", "type": "string"}]},
+            "primaryType": "Foo",
+            "domain": {"name": "x"},
+            "message": {"a": 1, "b": [{"c": "y"}]}
+        }"#;
+        let data = TypedData::from_json(json).unwrap();
+        assert_eq!(
+            Err(Error::InvalidTypedData("Foo.b: expected 2 elements".to_string())),
+            data.signing_hash()
+        );
+        let mut missing = data.clone();
+        missing.message = ::serde_json::from_str(r#"{"b": [{"c": "y"}, {"c": "z"}]}"#).unwrap();
+        assert_eq!(Err(Error::InvalidTypedData("Foo.a: missing value".to_string())), missing.signing_hash());
+        let mut undefined = data.clone();
+        undefined.primary_type = "Baz".to_string();
+        assert!(undefined.signing_hash().is_err());
+    }
+}
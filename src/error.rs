@@ -0,0 +1,136 @@
+use std::error;
+use std::fmt;
+use ethereum_types::H160;
+use rlp::DecoderError;
+use secp256k1;
+
+/// Errors that can occur while encoding or signing a transaction.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    /// The private key is zero or not below the secp256k1 curve order
+    InvalidPrivateKey,
+    /// The chain id is too large to be encoded into the `v` value of a signature
+    InvalidChainId(u64),
+    /// The message to be signed is not a 32 byte hash
+    InvalidMessage,
+    /// The transaction is for a different chain than the signer is bound to
+    ChainIdMismatch { expected: u64, found: Option<u64> },
+    /// The signature returned by a signer recovers to the given address instead of the
+    /// signer's own
+    SignerAddressMismatch(H160),
+    /// The signature is malformed or no public key can be recovered from it
+    InvalidSignature,
+    /// The signature s is above half the secp256k1 curve order, which EIP-2 forbids
+    HighS,
+    /// A blob transaction has no sidecar to encode or validate
+    MissingBlobSidecar,
+    /// The blob sidecar has mismatched item counts or wrongly sized items
+    InvalidBlobSidecar,
+    /// The versioned hash at the given index does not match its KZG commitment
+    BlobVersionedHashMismatch(usize),
+    /// The KZG proof at the given index does not verify against its blob and commitment
+    InvalidBlobProof(usize),
+    /// The blob transaction carries the given number of blobs, which is not between one and
+    /// `MAX_BLOBS_PER_TRANSACTION`
+    InvalidBlobCount(usize),
+    /// The encoded transaction is not valid RLP or has the wrong shape
+    Rlp(DecoderError),
+    /// The encoded transaction is followed by extra bytes
+    TrailingBytes,
+    /// The encoded transaction is not in its canonical RLP form
+    NonCanonicalEncoding,
+    /// The EIP-2718 envelope carries a type this crate does not know about
+    UnsupportedTransactionType(u8),
+    /// A remote signer could not be reached or gave an unusable response
+    Remote(String),
+    /// The keystore is malformed or uses an unsupported cipher or key derivation
+    InvalidKeystore(String),
+    /// The keystore MAC does not match, usually because the password is wrong
+    KeystoreMacMismatch,
+    /// The EIP-712 typed data is malformed or a value does not match its type
+    InvalidTypedData(String),
+    /// An ABI type or signature is malformed, or a value does not match its type
+    InvalidAbi(String),
+    /// The address is not 20 bytes of hex
+    InvalidAddress(String),
+    /// The mixed-case address does not match its EIP-55 or EIP-1191 checksum
+    InvalidAddressChecksum(String),
+    /// The amount is malformed, has an unknown unit or is a fraction of a wei
+    InvalidAmount(String),
+    /// The amount does not fit in 256 bits of wei
+    AmountOverflow(String),
+    /// The BIP-32 derivation path is malformed
+    InvalidDerivationPath(String),
+    /// The BIP-39 mnemonic has an unknown word, a wrong length or a bad checksum
+    InvalidMnemonic(String),
+    /// Reading or writing a file, or gathering randomness, failed
+    Io(String),
+    /// Any other failure reported by secp256k1
+    Secp256k1(secp256k1::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::InvalidPrivateKey => write!(f, "invalid private key"),
+            Error::InvalidChainId(id) => write!(f, "chain id {} is out of range", id),
+            Error::InvalidMessage => write!(f, "message is not a 32 byte hash"),
+            Error::ChainIdMismatch { expected, found: Some(found) } => {
+                write!(f, "transaction is for chain {} but the signer is bound to {}", found, expected)
+            }
+            Error::ChainIdMismatch { expected, found: None } => {
+                write!(f, "transaction has no chain id but the signer is bound to {}", expected)
+            }
+            Error::SignerAddressMismatch(address) => {
+                write!(f, "signature was made by unexpected account {:?}", address)
+            }
+            Error::InvalidSignature => write!(f, "invalid signature"),
+            Error::HighS => write!(f, "signature s is above secp256k1n/2"),
+            Error::MissingBlobSidecar => write!(f, "blob transaction has no sidecar"),
+            Error::InvalidBlobSidecar => write!(f, "malformed blob sidecar"),
+            Error::BlobVersionedHashMismatch(i) => {
+                write!(f, "versioned hash {} does not match its commitment", i)
+            }
+            Error::InvalidBlobProof(i) => write!(f, "KZG proof {} does not verify", i),
+            Error::InvalidBlobCount(n) => write!(f, "blob transaction carries {} blobs", n),
+            Error::Rlp(ref e) => write!(f, "malformed RLP: {}", e),
+            Error::TrailingBytes => write!(f, "trailing bytes after transaction"),
+            Error::NonCanonicalEncoding => write!(f, "transaction is not canonically encoded"),
+            Error::UnsupportedTransactionType(t) => write!(f, "unsupported transaction type {:#04x}", t),
+            Error::Remote(ref e) => write!(f, "remote signer: {}", e),
+            Error::InvalidKeystore(ref e) => write!(f, "invalid keystore: {}", e),
+            Error::KeystoreMacMismatch => write!(f, "keystore MAC mismatch, wrong password?"),
+            Error::InvalidTypedData(ref e) => write!(f, "invalid typed data: {}", e),
+            Error::InvalidAbi(ref e) => write!(f, "invalid ABI: {}", e),
+            Error::InvalidAddress(ref a) => write!(f, "invalid address {:?}", a),
+            Error::InvalidAddressChecksum(ref a) => write!(f, "bad checksum in address {:?}", a),
+            Error::InvalidAmount(ref a) => write!(f, "invalid amount {:?}", a),
+            Error::AmountOverflow(ref a) => write!(f, "amount {:?} does not fit in 256 bits", a),
+            Error::InvalidDerivationPath(ref p) => write!(f, "invalid derivation path {:?}", p),
+            Error::InvalidMnemonic(ref e) => write!(f, "invalid mnemonic: {}", e),
+            Error::Io(ref e) => write!(f, "{}", e),
+            Error::Secp256k1(ref e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+impl From<secp256k1::Error> for Error {
+    fn from(e: secp256k1::Error) -> Error {
+        match e {
+            secp256k1::Error::InvalidSecretKey => Error::InvalidPrivateKey,
+            secp256k1::Error::InvalidMessage => Error::InvalidMessage,
+            secp256k1::Error::InvalidSignature |
+            secp256k1::Error::InvalidRecoveryId |
+            secp256k1::Error::IncorrectSignature => Error::InvalidSignature,
+            e => Error::Secp256k1(e),
+        }
+    }
+}
+
+impl From<DecoderError> for Error {
+    fn from(e: DecoderError) -> Error {
+        Error::Rlp(e)
+    }
+}
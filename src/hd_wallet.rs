@@ -0,0 +1,453 @@
+use std::fmt;
+use std::str::FromStr;
+use ethereum_types::H256;
+use secp256k1::Secp256k1;
+use secp256k1::key::{PublicKey, SecretKey};
+use error::Error;
+use hmac::{Hmac, KeyInit, Mac};
+use pbkdf2::pbkdf2_hmac;
+use sha2::{Digest, Sha256, Sha512};
+use signer::LocalSigner;
+
+/// Offset of hardened child indices
+pub const HARDENED: u32 = 0x8000_0000;
+/// PBKDF2 iterations turning a BIP-39 mnemonic into a seed
+const MNEMONIC_ITERATIONS: u32 = 2048;
+/// Key of the HMAC deriving the BIP-32 master key from a seed
+const MASTER_KEY_SALT: &[u8] = b"Bitcoin seed";
+/// BIP-44 path of the first Ethereum account, without the address index
+const ETHEREUM_ACCOUNT_PATH: [u32; 4] = [44 | HARDENED, 60 | HARDENED, HARDENED, 0];
+/// The BIP-39 English wordlist, one word per line in sorted order
+const ENGLISH_WORDLIST: &str = include_str!("bip39_english.txt");
+/// Bits of the entropy and checksum each mnemonic word stands for
+const BITS_PER_WORD: usize = 11;
+
+/// Computes the BIP-39 seed of a mnemonic phrase and optional passphrase. The words are
+/// not checked (see `mnemonic_to_entropy`), and non-ASCII phrases must already be NFKD normalized.
+pub fn mnemonic_to_seed(mnemonic: &str, passphrase: &str) -> [u8; 64] {
+    let words: Vec<&str> = mnemonic.split_whitespace().collect();
+    let salt = format!("mnemonic{}", passphrase);
+    let mut seed = [0u8; 64];
+    pbkdf2_hmac::<Sha512>(words.join(" ").as_bytes(), salt.as_bytes(), MNEMONIC_ITERATIONS, &mut seed);
+    seed
+}
+
+/// Recovers the entropy a BIP-39 mnemonic phrase encodes, checking its words against the
+/// English wordlist and its checksum
+pub fn mnemonic_to_entropy(mnemonic: &str) -> Result<Vec<u8>, Error> {
+    let words: Vec<&str> = mnemonic.split_whitespace().collect();
+    if words.len() < 12 || words.len() > 24 || words.len() % 3 != 0 {
+        return Err(Error::InvalidMnemonic(format!("{} words, expected 12, 15, 18, 21 or 24", words.len())));
+    }
+    let wordlist: Vec<&str> = ENGLISH_WORDLIST.lines().collect();
+    let mut bits = Vec::with_capacity(words.len() * BITS_PER_WORD);
+    for word in words.iter() {
+        let index = wordlist.binary_search(word)
+            .map_err(|_| Error::InvalidMnemonic(format!("unknown word {:?}", word)))?;
+        bits.extend((0..BITS_PER_WORD).rev().map(|bit| (index >> bit) & 1 == 1));
+    }
+
+    // the entropy is followed by the first bits of its SHA-256, one per 32 bits of entropy
+    let (entropy_bits, checksum) = bits.split_at(bits.len() * 32 / 33);
+    let entropy: Vec<u8> = entropy_bits.chunks(8)
+        .map(|byte| byte.iter().fold(0u8, |acc, bit| acc << 1 | *bit as u8))
+        .collect();
+    let hash = Sha256::digest(&entropy);
+    let expected = (0..checksum.len()).map(|i| (hash[i / 8] >> (7 - i % 8)) & 1 == 1);
+    if !expected.eq(checksum.iter().cloned()) {
+        return Err(Error::InvalidMnemonic("checksum mismatch".to_string()));
+    }
+    Ok(entropy)
+}
+
+/// A BIP-32 derivation path such as `m/44'/60'/0'/0/0`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DerivationPath(Vec<u32>);
+
+impl DerivationPath {
+    /// The BIP-44 path of the Ethereum address at `index`, `m/44'/60'/0'/0/{index}`
+    pub fn ethereum(index: u32) -> DerivationPath {
+        let mut path = ETHEREUM_ACCOUNT_PATH.to_vec();
+        path.push(index);
+        DerivationPath(path)
+    }
+
+    /// Child indices from the master key down, hardened ones offset by `HARDENED`
+    pub fn indices(&self) -> &[u32] {
+        &self.0
+    }
+}
+
+impl FromStr for DerivationPath {
+    type Err = Error;
+
+    /// Parses a path; hardened indices may be marked with `'`, `h` or `H`
+    fn from_str(path: &str) -> Result<DerivationPath, Error> {
+        let invalid = || Error::InvalidDerivationPath(path.to_string());
+        let mut parts = path.trim().split('/');
+        if parts.next() != Some("m") {
+            return Err(invalid());
+        }
+        let mut indices = Vec::new();
+        for part in parts {
+            let (digits, hardened) = match part.strip_suffix(['\'', 'h', 'H']) {
+                Some(digits) => (digits, true),
+                None => (part, false)
+            };
+            if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(invalid());
+            }
+            let index: u32 = digits.parse().map_err(|_| invalid())?;
+            if index >= HARDENED {
+                return Err(invalid());
+            }
+            indices.push(if hardened { index | HARDENED } else { index });
+        }
+        Ok(DerivationPath(indices))
+    }
+}
+
+impl fmt::Display for DerivationPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "m")?;
+        for index in self.0.iter() {
+            if index & HARDENED != 0 {
+                write!(f, "/{}'", index & !HARDENED)?;
+            } else {
+                write!(f, "/{}", index)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A BIP-32 extended private key
+#[derive(Clone, PartialEq)]
+pub struct ExtendedPrivateKey {
+    private_key: H256,
+    chain_code: H256
+}
+
+impl ExtendedPrivateKey {
+    /// Derives the master key of a seed
+    pub fn from_seed(seed: &[u8]) -> Result<ExtendedPrivateKey, Error> {
+        ExtendedPrivateKey::from_hmac(&hmac_sha512(MASTER_KEY_SALT, seed))
+    }
+
+    /// Derives the child key at `index`; indices from `HARDENED` up are hardened
+    pub fn derive_child(&self, index: u32) -> Result<ExtendedPrivateKey, Error> {
+        let secp = Secp256k1::signing_only();
+        let secret = SecretKey::from_slice(&secp, &self.private_key.0)?;
+        let mut data = Vec::with_capacity(37);
+        if index & HARDENED != 0 {
+            data.push(0);
+            data.extend_from_slice(&self.private_key.0);
+        } else {
+            data.extend_from_slice(&PublicKey::from_secret_key(&secp, &secret).serialize());
+        }
+        data.extend_from_slice(&index.to_be_bytes());
+
+        let mut child = ExtendedPrivateKey::from_hmac(&hmac_sha512(&self.chain_code.0, &data))?;
+        let mut key = SecretKey::from_slice(&secp, &child.private_key.0)?;
+        key.add_assign(&secp, &secret)?;
+        child.private_key = H256::from(&key[..]);
+        Ok(child)
+    }
+
+    /// Derives the key at `path` below this one
+    pub fn derive_path(&self, path: &DerivationPath) -> Result<ExtendedPrivateKey, Error> {
+        let mut key = self.clone();
+        for index in path.indices() {
+            key = key.derive_child(*index)?;
+        }
+        Ok(key)
+    }
+
+    /// The private key
+    pub fn private_key(&self) -> H256 {
+        self.private_key
+    }
+
+    /// The chain code
+    pub fn chain_code(&self) -> H256 {
+        self.chain_code
+    }
+
+    // the left half of the HMAC becomes the key, the right half the chain code
+    fn from_hmac(i: &[u8; 64]) -> Result<ExtendedPrivateKey, Error> {
+        // rejects keys that are zero or not below the curve order
+        SecretKey::from_slice(&Secp256k1::without_caps(), &i[..32])?;
+        Ok(ExtendedPrivateKey {
+            private_key: H256::from(&i[..32]),
+            chain_code: H256::from(&i[32..])
+        })
+    }
+}
+
+// never print the private key
+impl fmt::Debug for ExtendedPrivateKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ExtendedPrivateKey")
+            .field("chain_code", &self.chain_code)
+            .finish()
+    }
+}
+
+/// Hierarchical deterministic wallet handing out a signer for each account
+#[derive(Debug, Clone)]
+pub struct HdWallet {
+    master: ExtendedPrivateKey
+}
+
+impl HdWallet {
+    /// Creates the wallet of a BIP-39 mnemonic phrase and optional passphrase. The words are
+    /// not checked; see `from_phrase_checked`.
+    pub fn from_mnemonic(mnemonic: &str, passphrase: &str) -> Result<HdWallet, Error> {
+        HdWallet::from_seed(&mnemonic_to_seed(mnemonic, passphrase))
+    }
+
+    /// Creates the wallet of a BIP-39 mnemonic phrase and optional passphrase, after checking
+    /// the phrase against the English wordlist and its checksum
+    pub fn from_phrase_checked(mnemonic: &str, passphrase: &str) -> Result<HdWallet, Error> {
+        mnemonic_to_entropy(mnemonic)?;
+        HdWallet::from_mnemonic(mnemonic, passphrase)
+    }
+
+    /// Creates the wallet of a BIP-32 seed
+    pub fn from_seed(seed: &[u8]) -> Result<HdWallet, Error> {
+        Ok(HdWallet { master: ExtendedPrivateKey::from_seed(seed)? })
+    }
+
+    /// Signer for the Ethereum address at `index`, derived at `m/44'/60'/0'/0/{index}`
+    pub fn signer(&self, index: u32) -> Result<LocalSigner, Error> {
+        self.signer_at(&DerivationPath::ethereum(index))
+    }
+
+    /// Signer for the key at an arbitrary derivation path
+    pub fn signer_at(&self, path: &DerivationPath) -> Result<LocalSigner, Error> {
+        LocalSigner::new(&self.master.derive_path(path)?.private_key())
+    }
+}
+
+fn hmac_sha512(key: &[u8], data: &[u8]) -> [u8; 64] {
+    let mut mac = Hmac::<Sha512>::new_from_slice(key).expect("HMAC takes keys of any length");
+    mac.update(data);
+    let mut out = [0u8; 64];
+    out.copy_from_slice(&mac.finalize().into_bytes());
+    out
+}
+
+mod test {
+
+    #[test]
+    fn test_mnemonic_to_seed() {
+        use rustc_hex::ToHex;
+        use hd_wallet::mnemonic_to_seed;
+
+        // BIP-39 reference vectors use the passphrase "TREZOR"
+        let mnemonic = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        assert_eq!(
+            "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531\
+             f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04",
+            mnemonic_to_seed(mnemonic, "TREZOR").to_hex::<String>()
+        );
+        assert_eq!(
+            mnemonic_to_seed(mnemonic, "TREZOR").to_vec(),
+            mnemonic_to_seed(&format!("  {}\n", mnemonic.replace(' ', "   ")), "TREZOR").to_vec()
+        );
+    }
+
+    #[test]
+    fn test_bip32_vector_1() {
+        use std::str::FromStr;
+        use ethereum_types::*;
+        use rustc_hex::FromHex;
+        use hd_wallet::{DerivationPath, ExtendedPrivateKey};
+
+        let seed: Vec<u8> = "000102030405060708090a0b0c0d0e0f".from_hex().unwrap();
+        let master = ExtendedPrivateKey::from_seed(&seed).unwrap();
+        let expected = vec![
+            ("m",
+             "0xe8f32e723decf4051aefac8e2c93c9c5b214313817cdb01a1494b917c8436b35",
+             "0x873dff81c02f525623fd1fe5167eac3a55a049de3d314bb42ee227ffed37d508"),
+            ("m/0'",
+             "0xedb2e14f9ee77d26dd93b4ecede8d16ed408ce149b6cd80b0715a2d911a0afea",
+             "0x47fdacbd0f1097043b78c63c20c34ef4ed9a111d980047ad16282c7ae6236141"),
+            ("m/0'/1",
+             "0x3c6cb8d0f6a264c91ea8b5030fadaa8e538b020f0a387421a12de9319dc93368",
+             "0x2a7857631386ba23dacac34180dd1983734e444fdbf774041578e9b6adb37c19"),
+            ("m/0'/1/2'",
+             "0xcbce0d719ecf7431d88e6a89fa1483e02e35092af60c042b1df2ff59fa424dca",
+             "0x04466b9cc8e161e966409ca52986c584f07e9dc81f735db683c3ff6ec7b1503f"),
+            ("m/0'/1/2'/2",
+             "0x0f479245fb19a38a1954c5c7c0ebab2f9bdfd96a17563ef28a6a4b1a2a764ef4",
+             "0xcfb71883f01676f587d023cc53a35bc7f88f724b1f8c2892ac1275ac822a3edd"),
+            ("m/0'/1/2'/2/1000000000",
+             "0x471b76e389e528d6de6d816857e012c5455051cad6660850e58372a6c3e6e7c8",
+             "0xc783e67b921d2beb8f6b389cc646d7263b4145701dadd2161548a8b078e65e9e"),
+        ];
+        for (path, private_key, chain_code) in expected.into_iter() {
+            let key = master.derive_path(&DerivationPath::from_str(path).unwrap()).unwrap();
+            assert_eq!(H256::from(private_key), key.private_key());
+            assert_eq!(H256::from(chain_code), key.chain_code());
+        }
+    }
+
+    #[test]
+    fn test_bip39_vectors() {
+        use std::fs::File;
+        use std::io::Read;
+        use ethereum_types::*;
+        use rustc_hex::FromHex;
+        use hd_wallet::{ExtendedPrivateKey, HdWallet, mnemonic_to_entropy, mnemonic_to_seed};
+        use serde_json;
+
+        // the trezor python-mnemonic English vectors, as published with the tests of tiny-bip39
+        // 2.0.0; the master keys are decoded from their xprv
+        #[derive(Deserialize)]
+        struct Vector {
+            entropy: String,
+            mnemonic: String,
+            seed: H512,
+            private_key: H256,
+            chain_code: H256
+        }
+
+        let mut file = File::open("./test/test_bip39_vectors.json").unwrap();
+        let mut f_string = String::new();
+        file.read_to_string(&mut f_string).unwrap();
+        let vectors: Vec<Vector> = serde_json::from_str(&f_string).unwrap();
+        for vector in vectors.into_iter() {
+            let mnemonic = &vector.mnemonic;
+            let entropy: Vec<u8> = vector.entropy[2..].from_hex().unwrap();
+            assert_eq!(entropy, mnemonic_to_entropy(mnemonic).unwrap(), "{}", mnemonic);
+            let seed = mnemonic_to_seed(mnemonic, "TREZOR");
+            assert_eq!(vector.seed, H512::from(&seed[..]), "{}", mnemonic);
+            let master = ExtendedPrivateKey::from_seed(&seed).unwrap();
+            assert_eq!(vector.private_key, master.private_key(), "{}", mnemonic);
+            assert_eq!(vector.chain_code, master.chain_code(), "{}", mnemonic);
+            assert!(HdWallet::from_phrase_checked(mnemonic, "TREZOR").is_ok(), "{}", mnemonic);
+        }
+    }
+
+    #[test]
+    fn test_rejects_invalid_mnemonics() {
+        use hd_wallet::{ENGLISH_WORDLIST, HdWallet, mnemonic_to_entropy};
+
+        assert_eq!(2048, ENGLISH_WORDLIST.lines().count());
+        let valid = "legal winner thank year wave sausage worth useful legal winner thank yellow";
+        assert!(mnemonic_to_entropy(valid).is_ok());
+        assert!(mnemonic_to_entropy(&format!(" {}\n", valid.replace(' ', "  "))).is_ok());
+
+        let invalid = [
+            // checksum mismatch after swapping the last two words
+            "legal winner thank year wave sausage worth useful legal winner yellow thank",
+            // checksum mismatch from tiny-bip39's validation tests
+            "bottle cannon west vanish seat ankle bicycle lucky bundle obey spatial purpose",
+            // not in the wordlist
+            "legal winner thank year wave sausage worth useful legal winner thank yelow",
+            "Legal winner thank year wave sausage worth useful legal winner thank yellow",
+            // wrong word counts
+            "legal winner thank year wave sausage worth useful legal winner thank",
+            "legal winner thank year wave sausage worth useful legal winner thank yellow abandon",
+            ""
+        ];
+        for mnemonic in invalid.iter() {
+            assert!(mnemonic_to_entropy(mnemonic).is_err(), "{:?} should not parse", mnemonic);
+            assert!(HdWallet::from_phrase_checked(mnemonic, "").is_err(), "{:?} should not parse", mnemonic);
+            assert!(HdWallet::from_mnemonic(mnemonic, "").is_ok());
+        }
+    }
+
+    #[test]
+    fn test_bip32_vectors_2_to_4() {
+        use std::str::FromStr;
+        use ethereum_types::*;
+        use rustc_hex::FromHex;
+        use hd_wallet::{DerivationPath, ExtendedPrivateKey};
+
+        // BIP-32 test vectors 2 to 4, with the keys decoded from the xprvs published with the
+        // tests of the bip32 0.6.0 crate; vectors 3 and 4 cover keys with leading zeros
+        let vectors = vec![
+            ("fffcf9f6f3f0edeae7e4e1dedbd8d5d2cfccc9c6c3c0bdbab7b4b1aeaba8a5a2\
+              9f9c999693908d8a8784817e7b7875726f6c696663605d5a5754514e4b484542", vec![
+                ("m",
+                 "0x4b03d6fc340455b363f51020ad3ecca4f0850280cf436c70c727923f6db46c3e",
+                 "0x60499f801b896d83179a4374aeb7822aaeaceaa0db1f85ee3e904c4defbd9689"),
+                ("m/0",
+                 "0xabe74a98f6c7eabee0428f53798f0ab8aa1bd37873999041703c742f15ac7e1e",
+                 "0xf0909affaa7ee7abe5dd4e100598d4dc53cd709d5a5c2cac40e7412f232f7c9c"),
+                ("m/0/2147483647'",
+                 "0x877c779ad9687164e9c2f4f0f4ff0340814392330693ce95a58fe18fd52e6e93",
+                 "0xbe17a268474a6bb9c61e1d720cf6215e2a88c5406c4aee7b38547f585c9a37d9"),
+                ("m/0/2147483647'/1",
+                 "0x704addf544a06e5ee4bea37098463c23613da32020d604506da8c0518e1da4b7",
+                 "0xf366f48f1ea9f2d1d3fe958c95ca84ea18e4c4ddb9366c336c927eb246fb38cb"),
+                ("m/0/2147483647'/1/2147483646'",
+                 "0xf1c7c871a54a804afe328b4c83a1c33b8e5ff48f5087273f04efa83b247d6a2d",
+                 "0x637807030d55d01f9a0cb3a7839515d796bd07706386a6eddf06cc29a65a0e29"),
+                ("m/0/2147483647'/1/2147483646'/2",
+                 "0xbb7d39bdb83ecf58f2fd82b6d918341cbef428661ef01ab97c28a4842125ac23",
+                 "0x9452b549be8cea3ecb7a84bec10dcfd94afe4d129ebfd3b3cb58eedf394ed271"),
+            ]),
+            ("4b381541583be4423346c643850da4b320e46a87ae3d2a4e6da11eba819cd4ac\
+              ba45d239319ac14f863b8d5ab5a0d0c64d2e8a1e7d1457df2e5a3c51c73235be", vec![
+                ("m",
+                 "0x00ddb80b067e0d4993197fe10f2657a844a384589847602d56f0c629c81aae32",
+                 "0x01d28a3e53cffa419ec122c968b3259e16b65076495494d97cae10bbfec3c36f"),
+                ("m/0'",
+                 "0x491f7a2eebc7b57028e0d3faa0acda02e75c33b03c48fb288c41e2ea44e1daef",
+                 "0xe5fea12a97b927fc9dc3d2cb0d1ea1cf50aa5a1fdc1f933e8906bb38df3377bd"),
+            ]),
+            ("3ddd5602285899a946114506157c7997e5444528f3003f6134712147db19b678", vec![
+                ("m",
+                 "0x12c0d59c7aa3a10973dbd3f478b65f2516627e3fe61e00c345be9a477ad2e215",
+                 "0xd0c8a1f6edf2500798c3e0b54f1b56e45f6d03e6076abd36e5e2f54101e44ce6"),
+                ("m/0'",
+                 "0x00d948e9261e41362a688b916f297121ba6bfb2274a3575ac0e456551dfd7f7e",
+                 "0xcdc0f06456a14876c898790e0b3b1a41c531170aec69da44ff7b7265bfe7743b"),
+                ("m/0'/1'",
+                 "0x3a2086edd7d9df86c3487a5905a1712a9aa664bce8cc268141e07549eaa8661d",
+                 "0xa48ee6674c5264a237703fd383bccd9fad4d9378ac98ab05e6e7029b06360c0d"),
+            ]),
+        ];
+        for (seed, expected) in vectors.into_iter() {
+            let seed: Vec<u8> = seed.from_hex().unwrap();
+            let master = ExtendedPrivateKey::from_seed(&seed).unwrap();
+            for (path, private_key, chain_code) in expected.into_iter() {
+                let key = master.derive_path(&DerivationPath::from_str(path).unwrap()).unwrap();
+                assert_eq!(H256::from(private_key), key.private_key(), "{}", path);
+                assert_eq!(H256::from(chain_code), key.chain_code(), "{}", path);
+            }
+        }
+    }
+
+    #[test]
+    fn test_derivation_paths() {
+        use std::str::FromStr;
+        use hd_wallet::{DerivationPath, HARDENED};
+
+        let path = DerivationPath::from_str("m/44'/60'/0'/0/7").unwrap();
+        assert_eq!(&[44 | HARDENED, 60 | HARDENED, HARDENED, 0, 7], path.indices());
+        assert_eq!(DerivationPath::ethereum(7), path);
+        assert_eq!("m/44'/60'/0'/0/7", path.to_string());
+        assert_eq!(path, DerivationPath::from_str("m/44h/60H/0'/0/7").unwrap());
+        assert_eq!(0, DerivationPath::from_str("m").unwrap().indices().len());
+
+        for invalid in ["", "44'/60'", "m/", "m//1", "m/-1", "m/+1", "m/1''", "m/2147483648", "m/x"].iter() {
+            assert!(DerivationPath::from_str(invalid).is_err(), "{} should not parse", invalid);
+        }
+    }
+
+    #[test]
+    fn test_hd_wallet_signers() {
+        use ethereum_types::*;
+        use hd_wallet::HdWallet;
+        use signer::Signer;
+
+        // the well-known development mnemonic used by Hardhat and Anvil
+        let wallet = HdWallet::from_mnemonic("test test test test test test test test test test test junk", "").unwrap();
+        assert_eq!(H160::from("0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266"), wallet.signer(0).unwrap().address());
+        assert_eq!(H160::from("0x70997970c51812dc3a010c7d01b50e0d17dc79c8"), wallet.signer(1).unwrap().address());
+    }
+}
@@ -0,0 +1,226 @@
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::str;
+use std::time::{Duration, Instant};
+use error::Error;
+
+/// Largest response accepted from a server, headers included
+const MAX_RESPONSE_SIZE: usize = 1 << 20;
+
+/// Status and body of an HTTP response
+pub(crate) struct Response {
+    pub status: u16,
+    pub body: Vec<u8>
+}
+
+/// Sends a POST request to a plain `http://` URL and waits for the whole response, which
+/// must arrive within `timeout` and be at most `MAX_RESPONSE_SIZE` bytes. The connection is
+/// closed afterwards.
+pub(crate) fn post(url: &str, content_type: &str, body: &[u8], timeout: Duration) -> Result<Response, Error> {
+    let deadline = Instant::now() + timeout;
+    let rest = url.strip_prefix("http://")
+        .ok_or_else(|| Error::Remote(format!("unsupported URL {}, only http:// is supported", url)))?;
+    let (authority, path) = match rest.find('/') {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, "/")
+    };
+    let has_port = authority.rfind(':').is_some_and(|i| i > authority.rfind(']').unwrap_or(0));
+    let address = if has_port { authority.to_string() } else { format!("{}:80", authority) };
+
+    let mut stream = connect(&address, deadline)?;
+    stream.set_write_timeout(Some(remaining(deadline)?)).map_err(io_error)?;
+    let head = format!(
+        "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: {}\r\nAccept: */*\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        path, authority, content_type, body.len()
+    );
+    stream.write_all(head.as_bytes()).map_err(io_error)?;
+    stream.write_all(body).map_err(io_error)?;
+    stream.flush().map_err(io_error)?;
+
+    // the socket timeouts apply to each read, so they are renewed with whatever time is left
+    let mut response = Vec::new();
+    let mut buf = [0u8; 4096];
+    loop {
+        stream.set_read_timeout(Some(remaining(deadline)?)).map_err(io_error)?;
+        let read = match stream.read(&mut buf) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock || e.kind() == io::ErrorKind::TimedOut => {
+                return Err(timed_out());
+            }
+            Err(e) => return Err(io_error(e))
+        };
+        if response.len() + read > MAX_RESPONSE_SIZE {
+            return Err(Error::Remote(format!("response is larger than {} bytes", MAX_RESPONSE_SIZE)));
+        }
+        response.extend_from_slice(&buf[..read]);
+    }
+    parse_response(&response)
+}
+
+/// Connects to the first address `address` resolves to that accepts before `deadline`
+fn connect(address: &str, deadline: Instant) -> Result<TcpStream, Error> {
+    let mut last_error = None;
+    for addr in address.to_socket_addrs().map_err(io_error)? {
+        match TcpStream::connect_timeout(&addr, remaining(deadline)?) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last_error = Some(e)
+        }
+    }
+    Err(match last_error {
+        Some(e) => io_error(e),
+        None => Error::Remote(format!("{} resolves to no address", address))
+    })
+}
+
+/// Time left until `deadline`, or an error once it has passed
+fn remaining(deadline: Instant) -> Result<Duration, Error> {
+    match deadline.checked_duration_since(Instant::now()) {
+        Some(left) if left > Duration::from_millis(0) => Ok(left),
+        _ => Err(timed_out())
+    }
+}
+
+fn timed_out() -> Error {
+    Error::Remote("request timed out".to_string())
+}
+
+fn parse_response(response: &[u8]) -> Result<Response, Error> {
+    let malformed = || Error::Remote("malformed HTTP response".to_string());
+    let end = response.windows(4).position(|w| w == b"\r\n\r\n").ok_or_else(malformed)?;
+    let head = str::from_utf8(&response[..end]).map_err(|_| malformed())?;
+    let mut lines = head.split("\r\n");
+    let status = lines.next()
+        .and_then(|line| line.split(' ').nth(1))
+        .and_then(|code| code.parse().ok())
+        .ok_or_else(malformed)?;
+
+    let mut chunked = false;
+    let mut length = None;
+    for line in lines {
+        let mut parts = line.splitn(2, ':');
+        let name = parts.next().unwrap_or("").trim().to_ascii_lowercase();
+        let value = parts.next().unwrap_or("").trim();
+        if name == "transfer-encoding" {
+            chunked = value.to_ascii_lowercase().contains("chunked");
+        } else if name == "content-length" {
+            length = Some(value.parse::<usize>().map_err(|_| malformed())?);
+        }
+    }
+
+    let body = &response[end + 4..];
+    let body = if chunked {
+        dechunk(body).ok_or_else(malformed)?
+    } else if let Some(length) = length {
+        body.get(..length).ok_or_else(malformed)?.to_vec()
+    } else {
+        body.to_vec()
+    };
+    Ok(Response { status, body })
+}
+
+fn dechunk(mut body: &[u8]) -> Option<Vec<u8>> {
+    let mut out = Vec::new();
+    loop {
+        let line_end = body.windows(2).position(|w| w == b"\r\n")?;
+        let line = str::from_utf8(&body[..line_end]).ok()?;
+        let size = usize::from_str_radix(line.split(';').next()?.trim(), 16).ok()?;
+        body = &body[line_end + 2..];
+        if size == 0 {
+            return Some(out);
+        }
+        out.extend_from_slice(body.get(..size)?);
+        body = body.get(size + 2..)?;
+    }
+}
+
+fn io_error(e: io::Error) -> Error {
+    Error::Remote(e.to_string())
+}
+
+mod test {
+
+    #[test]
+    fn test_connects_within_timeout() {
+        use std::net::TcpListener;
+        use std::time::{Duration, Instant};
+        use http::connect;
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        assert!(connect(&address, deadline).is_ok());
+
+        drop(listener);
+        assert!(connect(&address, deadline).is_err());
+        assert!(connect("localhost:0", deadline).is_err());
+        assert!(connect(&address, Instant::now()).is_err());
+    }
+
+    #[test]
+    fn test_limits_whole_response() {
+        use std::io::{Read, Write};
+        use std::net::TcpListener;
+        use std::thread;
+        use std::time::{Duration, Instant};
+        use error::Error;
+        use http::post;
+
+        // answers each request with `head` and then `body`, one chunk at a time
+        fn serve(head: &'static [u8], chunk: Vec<u8>, chunks: usize, pause: Duration) -> String {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let url = format!("http://{}/", listener.local_addr().unwrap());
+            thread::spawn(move || {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut buf = [0u8; 4096];
+                let _ = stream.read(&mut buf);
+                let _ = stream.write_all(head);
+                for _ in 0..chunks {
+                    if stream.write_all(&chunk).is_err() {
+                        return;
+                    }
+                    thread::sleep(pause);
+                }
+            });
+            url
+        }
+
+        // a server that never stops sending
+        let url = serve(b"HTTP/1.1 200 OK\r\n\r\n", vec![b'a'; 64 * 1024], 1000, Duration::from_millis(0));
+        assert_eq!(
+            Some(Error::Remote("response is larger than 1048576 bytes".to_string())),
+            post(&url, "text/plain", b"", Duration::from_secs(30)).err()
+        );
+
+        // a server that trickles one byte at a time, each well within the timeout
+        let url = serve(b"HTTP/1.1 200 OK\r\n\r\n", vec![b'a'], 100, Duration::from_millis(50));
+        let start = Instant::now();
+        assert_eq!(
+            Some(Error::Remote("request timed out".to_string())),
+            post(&url, "text/plain", b"", Duration::from_millis(500)).err()
+        );
+        assert!(start.elapsed() < Duration::from_secs(3));
+
+        let url = serve(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\n", b"ok".to_vec(), 1, Duration::from_millis(0));
+        assert_eq!(b"ok".to_vec(), post(&url, "text/plain", b"", Duration::from_secs(5)).unwrap().body);
+    }
+
+    #[test]
+    fn test_parses_responses() {
+        use http::parse_response;
+
+        let response = parse_response(b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello").unwrap();
+        assert_eq!(200, response.status);
+        assert_eq!(b"hello".to_vec(), response.body);
+
+        let response = parse_response(
+            b"HTTP/1.1 404 Not Found\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nhel\r\n2;x=y\r\nlo\r\n0\r\n\r\n"
+        ).unwrap();
+        assert_eq!(404, response.status);
+        assert_eq!(b"hello".to_vec(), response.body);
+
+        assert!(parse_response(b"HTTP/1.1 200 OK\r\nContent-Length: 6\r\n\r\nhello").is_err());
+        assert!(parse_response(b"garbage").is_err());
+    }
+}
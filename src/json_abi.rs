@@ -0,0 +1,349 @@
+use std::fs;
+use std::path::Path;
+use serde_json::{self, Value};
+use abi::{decode, decode_revert, encode_typed, FunctionSignature, ParamType, Revert, Token};
+use error::Error;
+
+/// A contract interface loaded from a Solidity JSON ABI
+#[derive(Debug, Clone, PartialEq)]
+pub struct Abi {
+    pub constructor: Option<Constructor>,
+    pub functions: Vec<Function>,
+    pub errors: Vec<AbiError>
+}
+
+/// A named function, constructor or error parameter
+#[derive(Debug, Clone, PartialEq)]
+pub struct Param {
+    pub name: String,
+    pub kind: ParamType
+}
+
+/// Whether a function reads or writes state and accepts ether
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateMutability {
+    Pure,
+    View,
+    NonPayable,
+    Payable
+}
+
+/// The constructor of a contract
+#[derive(Debug, Clone, PartialEq)]
+pub struct Constructor {
+    pub inputs: Vec<Param>,
+    pub state_mutability: StateMutability
+}
+
+/// A function of a contract
+#[derive(Debug, Clone, PartialEq)]
+pub struct Function {
+    pub name: String,
+    pub inputs: Vec<Param>,
+    pub outputs: Vec<Param>,
+    pub state_mutability: StateMutability
+}
+
+/// A custom error of a contract
+#[derive(Debug, Clone, PartialEq)]
+pub struct AbiError {
+    pub name: String,
+    pub inputs: Vec<Param>
+}
+
+#[derive(Deserialize)]
+struct RawEntry {
+    #[serde(rename = "type", default = "function_type")]
+    kind: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    inputs: Vec<RawParam>,
+    #[serde(default)]
+    outputs: Vec<RawParam>,
+    #[serde(rename = "stateMutability")]
+    state_mutability: Option<String>,
+    #[serde(default)]
+    constant: bool,
+    #[serde(default)]
+    payable: bool
+}
+
+#[derive(Deserialize)]
+struct RawParam {
+    #[serde(default)]
+    name: String,
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    components: Vec<RawParam>
+}
+
+fn function_type() -> String {
+    "function".to_string()
+}
+
+impl Abi {
+    /// Parses a JSON ABI, either the bare array or a build artifact with an `abi` field
+    pub fn from_json(json: &str) -> Result<Abi, Error> {
+        let mut value: Value = serde_json::from_str(json).map_err(|e| Error::InvalidAbi(e.to_string()))?;
+        if let Some(abi) = value.get_mut("abi") {
+            value = abi.take();
+        }
+        let entries: Vec<RawEntry> = serde_json::from_value(value).map_err(|e| Error::InvalidAbi(e.to_string()))?;
+
+        let mut abi = Abi { constructor: None, functions: Vec::new(), errors: Vec::new() };
+        for entry in entries {
+            match entry.kind.as_str() {
+                "constructor" => abi.constructor = Some(Constructor {
+                    inputs: params(&entry.inputs)?,
+                    state_mutability: entry.mutability()?
+                }),
+                "function" => abi.functions.push(Function {
+                    state_mutability: entry.mutability()?,
+                    inputs: params(&entry.inputs)?,
+                    outputs: params(&entry.outputs)?,
+                    name: entry.name
+                }),
+                "error" => abi.errors.push(AbiError { inputs: params(&entry.inputs)?, name: entry.name }),
+                "event" | "fallback" | "receive" => {}
+                other => return Err(Error::InvalidAbi(format!("unknown entry type {}", other)))
+            }
+        }
+        Ok(abi)
+    }
+
+    /// Reads a JSON ABI file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Abi, Error> {
+        let json = fs::read_to_string(path).map_err(|e| Error::Io(e.to_string()))?;
+        Abi::from_json(&json)
+    }
+
+    /// Returns the function with the given selector
+    pub fn function_by_selector(&self, selector: [u8; 4]) -> Option<&Function> {
+        self.functions.iter().find(|f| f.selector() == selector)
+    }
+
+    /// Finds the function called by `data` and decodes its arguments
+    pub fn decode_input(&self, data: &[u8]) -> Result<(&Function, Vec<Token>), Error> {
+        if data.len() < 4 {
+            return Err(Error::InvalidAbi("calldata is shorter than a selector".to_string()));
+        }
+        let function = self.function_by_selector([data[0], data[1], data[2], data[3]])
+            .ok_or_else(|| Error::InvalidAbi(format!("no function with selector 0x{:02x}{:02x}{:02x}{:02x}",
+                                                     data[0], data[1], data[2], data[3])))?;
+        Ok((function, function.decode_input(data)?))
+    }
+
+    /// Decodes revert data, resolving the custom errors declared in the ABI
+    pub fn decode_revert(&self, data: &[u8]) -> Result<Revert, Error> {
+        if data.len() >= 4 {
+            if let Some(error) = self.errors.iter().find(|e| e.selector()[..] == data[..4]) {
+                return Ok(Revert::Custom { name: error.name.clone(), args: error.decode(data)? });
+            }
+        }
+        decode_revert(data)
+    }
+}
+
+impl RawEntry {
+    fn mutability(&self) -> Result<StateMutability, Error> {
+        match self.state_mutability.as_deref() {
+            Some("pure") => Ok(StateMutability::Pure),
+            Some("view") => Ok(StateMutability::View),
+            Some("nonpayable") => Ok(StateMutability::NonPayable),
+            Some("payable") => Ok(StateMutability::Payable),
+            Some(other) => Err(Error::InvalidAbi(format!("unknown state mutability {}", other))),
+            // ABIs from before Solidity 0.4.16 only carry these flags
+            None if self.payable => Ok(StateMutability::Payable),
+            None if self.constant => Ok(StateMutability::View),
+            None => Ok(StateMutability::NonPayable)
+        }
+    }
+}
+
+impl Constructor {
+    /// Returns contract creation code, `bytecode` followed by the encoded constructor
+    /// arguments
+    pub fn encode_input(&self, bytecode: &[u8], args: &[Token]) -> Result<Vec<u8>, Error> {
+        if self.inputs.len() != args.len() {
+            return Err(Error::InvalidAbi(format!(
+                "constructor takes {} arguments, {} given", self.inputs.len(), args.len()
+            )));
+        }
+        let mut data = bytecode.to_vec();
+        data.extend_from_slice(&encode_typed(&kinds(&self.inputs), args)?);
+        Ok(data)
+    }
+}
+
+impl Function {
+    /// Returns the canonical signature, e.g. `transfer(address,uint256)`
+    pub fn signature(&self) -> String {
+        self.to_signature().signature()
+    }
+
+    /// Returns the 4-byte selector
+    pub fn selector(&self) -> [u8; 4] {
+        self.to_signature().selector()
+    }
+
+    /// Encodes calldata calling this function with the given arguments
+    pub fn encode_input(&self, args: &[Token]) -> Result<Vec<u8>, Error> {
+        self.to_signature().encode_input(args)
+    }
+
+    /// Decodes the arguments of calldata calling this function
+    pub fn decode_input(&self, data: &[u8]) -> Result<Vec<Token>, Error> {
+        self.to_signature().decode_input(data)
+    }
+
+    /// Decodes the data returned by a call to this function
+    pub fn decode_output(&self, data: &[u8]) -> Result<Vec<Token>, Error> {
+        decode(&kinds(&self.outputs), data)
+    }
+
+    /// Formats a call for people to read, e.g. `transfer(to: 0x…, amount: 100)`
+    pub fn format_call(&self, args: &[Token]) -> String {
+        let args = self.inputs.iter().zip(args.iter()).map(|(param, arg)| {
+            if param.name.is_empty() { arg.to_string() } else { format!("{}: {}", param.name, arg) }
+        });
+        format!("{}({})", self.name, args.collect::<Vec<_>>().join(", "))
+    }
+
+    fn to_signature(&self) -> FunctionSignature {
+        FunctionSignature { name: self.name.clone(), inputs: kinds(&self.inputs) }
+    }
+}
+
+impl AbiError {
+    /// Returns the 4-byte selector
+    pub fn selector(&self) -> [u8; 4] {
+        FunctionSignature { name: self.name.clone(), inputs: kinds(&self.inputs) }.selector()
+    }
+
+    /// Decodes the arguments of revert data raising this error
+    pub fn decode(&self, data: &[u8]) -> Result<Vec<Token>, Error> {
+        FunctionSignature { name: self.name.clone(), inputs: kinds(&self.inputs) }.decode_input(data)
+    }
+}
+
+fn params(raw: &[RawParam]) -> Result<Vec<Param>, Error> {
+    raw.iter().map(|p| Ok(Param { name: p.name.clone(), kind: param_type(p)? })).collect()
+}
+
+/// Resolves `tuple`, `tuple[]`, `tuple[2][]`… against the parameter's components
+fn param_type(raw: &RawParam) -> Result<ParamType, Error> {
+    if raw.kind.starts_with("tuple") {
+        let components = params(&raw.components)?;
+        let tuple = ParamType::Tuple(kinds(&components));
+        return format!("{}{}", tuple, &raw.kind["tuple".len()..]).parse();
+    }
+    raw.kind.parse()
+}
+
+fn kinds(params: &[Param]) -> Vec<ParamType> {
+    params.iter().map(|p| p.kind.clone()).collect()
+}
+
+mod test {
+    #[test]
+    fn test_loads_json_abi() {
+        use abi::ParamType;
+        use json_abi::{Abi, StateMutability};
+
+        let abi = Abi::load("./test/test_abi_token.json").unwrap();
+
+        let constructor = abi.constructor.as_ref().unwrap();
+        assert_eq!(vec![ParamType::String, ParamType::Uint(256)],
+                   constructor.inputs.iter().map(|p| p.kind.clone()).collect::<Vec<_>>());
+        assert_eq!(
+            vec![
+                "balanceOf(address)",
+                "transfer(address,uint256)",
+                "transfer(address,uint256,bytes)",
+                "batchTransfer((address,uint256)[])",
+                "deposit()",
+                "symbol()"
+            ],
+            abi.functions.iter().map(|f| f.signature()).collect::<Vec<_>>()
+        );
+        assert_eq!(StateMutability::View, abi.functions[0].state_mutability);
+        assert_eq!(StateMutability::Payable, abi.functions[4].state_mutability);
+        assert_eq!(StateMutability::View, abi.functions[5].state_mutability);
+        assert_eq!("InsufficientBalance", abi.errors[0].name);
+
+        let artifact = format!("{{\"contractName\":\"Token\",\"abi\":{}}}",
+                               ::std::fs::read_to_string("./test/test_abi_token.json").unwrap());
+        assert_eq!(abi, Abi::from_json(&artifact).unwrap());
+        assert!(Abi::from_json("[{\"type\":\"function\",\"name\":\"f\",\"inputs\":[{\"type\":\"uint7\"}]}]").is_err());
+    }
+
+    #[test]
+    fn test_decodes_calls() {
+        use ethereum_types::*;
+        use rustc_hex::FromHex;
+        use abi::Token;
+        use json_abi::Abi;
+
+        let abi = Abi::load("./test/test_abi_token.json").unwrap();
+
+        let data: Vec<u8> = "a9059cbb\
+            0000000000000000000000003535353535353535353535353535353535353535\
+            0000000000000000000000000000000000000000000000000de0b6b3a7640000".from_hex().unwrap();
+        let (function, args) = abi.decode_input(&data).unwrap();
+        assert_eq!("transfer(address,uint256)", function.signature());
+        assert_eq!(
+            vec![Token::Address(H160::from(&[0x35; 20][..])), Token::Uint(U256::from(1_000_000_000_000_000_000u64))],
+            args
+        );
+        assert_eq!(
+            "transfer(to: 0x3535353535353535353535353535353535353535, amount: 1000000000000000000)",
+            function.format_call(&args)
+        );
+
+        let transfers = Token::Array(vec![
+            Token::Tuple(vec![Token::Address(H160::from(1)), Token::Uint(U256::from(2))]),
+            Token::Tuple(vec![Token::Address(H160::from(3)), Token::Uint(U256::from(4))])
+        ]);
+        let batch = &abi.functions[3];
+        let data = batch.encode_input(::std::slice::from_ref(&transfers)).unwrap();
+        let (function, args) = abi.decode_input(&data).unwrap();
+        assert_eq!(batch, function);
+        assert_eq!(vec![transfers], args);
+
+        let output: Vec<u8> = "0000000000000000000000000000000000000000000000000000000000000001".from_hex().unwrap();
+        assert_eq!(vec![Token::Bool(true)], abi.functions[1].decode_output(&output).unwrap());
+
+        assert!(abi.decode_input(&[0xde, 0xad, 0xbe, 0xef]).is_err());
+        assert!(abi.decode_input(&data[..3]).is_err());
+    }
+
+    #[test]
+    fn test_decodes_custom_errors() {
+        use ethereum_types::*;
+        use rustc_hex::FromHex;
+        use abi::{Revert, Token};
+        use json_abi::Abi;
+
+        let abi = Abi::load("./test/test_abi_token.json").unwrap();
+
+        // InsufficientBalance(uint256,uint256)
+        let data: Vec<u8> = "cf479181\
+            0000000000000000000000000000000000000000000000000000000000000064\
+            00000000000000000000000000000000000000000000000000000000000000c8".from_hex().unwrap();
+        let revert = abi.decode_revert(&data).unwrap();
+        assert_eq!(
+            Revert::Custom {
+                name: "InsufficientBalance".to_string(),
+                args: vec![Token::Uint(U256::from(100)), Token::Uint(U256::from(200))]
+            },
+            revert
+        );
+        assert_eq!("reverted: InsufficientBalance(100, 200)", revert.to_string());
+
+        let panic: Vec<u8> = "4e487b71\
+            0000000000000000000000000000000000000000000000000000000000000001".from_hex().unwrap();
+        assert_eq!(Revert::Panic(U256::one()), abi.decode_revert(&panic).unwrap());
+    }
+}
@@ -0,0 +1,438 @@
+use std::fs;
+use std::path::Path;
+use aes::Aes128;
+use ctr::Ctr128BE;
+use ctr::cipher::{KeyIvInit, StreamCipher};
+use ethereum_types::{H160, H256};
+use rand::{OsRng, Rng};
+use pbkdf2::pbkdf2_hmac;
+use rustc_hex::FromHex;
+use scrypt::{scrypt, Params as ScryptParams};
+use serde_json;
+use sha2::Sha256;
+use subtle::ConstantTimeEq;
+use address::address_from_private_key;
+use error::Error;
+use signature::keccak256_hash;
+
+/// Version of the Web3 Secret Storage format that is read and written
+const KEYSTORE_VERSION: u32 = 3;
+/// Length of the derived key; the first half encrypts, the second half authenticates
+const DERIVED_KEY_LENGTH: usize = 32;
+/// Upper bound on the memory scrypt parameters may ask for, to refuse hostile files
+const MAX_SCRYPT_MEMORY: u128 = 1 << 30;
+/// Upper bound on the scrypt work `n * r * p`, which geth's standard parameters reach
+const MAX_SCRYPT_WORK: u128 = (1 << 18) * 8;
+/// Upper bound on PBKDF2 iterations, 16 times the 262144 geth uses
+const MAX_PBKDF2_ITERATIONS: u32 = 1 << 22;
+
+/// Key derivation function protecting a keystore
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KeystoreKdf {
+    /// scrypt with cost `n`, block size `r` and parallelism `p`
+    Scrypt { n: u32, r: u32, p: u32 },
+    /// PBKDF2 with HMAC-SHA256 and `c` iterations
+    Pbkdf2 { c: u32 }
+}
+
+impl KeystoreKdf {
+    /// The scrypt parameters geth uses for its light keystores, cheap enough for tests or
+    /// constrained devices
+    pub fn light() -> KeystoreKdf {
+        KeystoreKdf::Scrypt { n: 1 << 12, r: 8, p: 6 }
+    }
+}
+
+impl Default for KeystoreKdf {
+    /// The scrypt parameters geth uses for its standard keystores
+    fn default() -> KeystoreKdf {
+        KeystoreKdf::Scrypt { n: 1 << 18, r: 8, p: 1 }
+    }
+}
+
+/// An encrypted private key in the Web3 Secret Storage (version 3) JSON format used by
+/// geth and most wallets
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Keystore {
+    #[serde(alias = "Crypto")]
+    crypto: CryptoJson,
+    id: String,
+    version: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    address: Option<String>
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+struct CryptoJson {
+    cipher: String,
+    cipherparams: CipherParams,
+    #[serde(with = "hex_bytes")]
+    ciphertext: Vec<u8>,
+    kdf: String,
+    kdfparams: KdfParams,
+    #[serde(with = "hex_bytes")]
+    mac: Vec<u8>
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+struct CipherParams {
+    #[serde(with = "hex_bytes")]
+    iv: Vec<u8>
+}
+
+/// Parameters of either KDF; which fields are present depends on `CryptoJson::kdf`
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+struct KdfParams {
+    dklen: usize,
+    #[serde(with = "hex_bytes")]
+    salt: Vec<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    n: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    r: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    p: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    c: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    prf: Option<String>
+}
+
+impl Keystore {
+    /// Encrypts `private_key` under `password` with a random salt, IV and id
+    pub fn encrypt(private_key: &H256, password: &str, kdf: KeystoreKdf) -> Result<Keystore, Error> {
+        let mut rng = OsRng::new().map_err(|e| Error::Io(e.to_string()))?;
+        let mut salt = [0u8; 32];
+        let mut iv = [0u8; 16];
+        let mut id = [0u8; 16];
+        rng.fill_bytes(&mut salt);
+        rng.fill_bytes(&mut iv);
+        rng.fill_bytes(&mut id);
+        Keystore::encrypt_with(private_key, password, kdf, &salt, iv, id)
+    }
+
+    fn encrypt_with(private_key: &H256, password: &str, kdf: KeystoreKdf, salt: &[u8], iv: [u8; 16], id: [u8; 16])
+        -> Result<Keystore, Error> {
+        let address = address_from_private_key(private_key)?;
+        let kdfparams = match kdf {
+            KeystoreKdf::Scrypt { n, r, p } => KdfParams {
+                dklen: DERIVED_KEY_LENGTH,
+                salt: salt.to_vec(),
+                n: Some(n),
+                r: Some(r),
+                p: Some(p),
+                c: None,
+                prf: None
+            },
+            KeystoreKdf::Pbkdf2 { c } => KdfParams {
+                dklen: DERIVED_KEY_LENGTH,
+                salt: salt.to_vec(),
+                n: None,
+                r: None,
+                p: None,
+                c: Some(c),
+                prf: Some("hmac-sha256".to_string())
+            }
+        };
+        let derived = derive_key(password, &kdfparams, kdf)?;
+        let mut key = [0u8; 16];
+        key.copy_from_slice(&derived[..16]);
+        let ciphertext = aes128_ctr(&key, &iv, &private_key.0);
+        let mac = mac(&derived, &ciphertext);
+        Ok(Keystore {
+            crypto: CryptoJson {
+                cipher: "aes-128-ctr".to_string(),
+                cipherparams: CipherParams { iv: iv.to_vec() },
+                ciphertext,
+                kdf: match kdf {
+                    KeystoreKdf::Scrypt { .. } => "scrypt",
+                    KeystoreKdf::Pbkdf2 { .. } => "pbkdf2"
+                }.to_string(),
+                kdfparams,
+                mac
+            },
+            id: format_uuid(id),
+            version: KEYSTORE_VERSION,
+            address: Some(format!("{:x}", address))
+        })
+    }
+
+    /// Decrypts the private key, failing with `Error::KeystoreMacMismatch` if the password
+    /// is wrong
+    pub fn decrypt(&self, password: &str) -> Result<H256, Error> {
+        if self.version != KEYSTORE_VERSION {
+            return Err(Error::InvalidKeystore(format!("unsupported version {}", self.version)));
+        }
+        let crypto = &self.crypto;
+        if crypto.cipher != "aes-128-ctr" {
+            return Err(Error::InvalidKeystore(format!("unsupported cipher {}", crypto.cipher)));
+        }
+        if crypto.cipherparams.iv.len() != 16 {
+            return Err(Error::InvalidKeystore("IV must be 16 bytes".to_string()));
+        }
+        let params = &crypto.kdfparams;
+        let kdf = match &crypto.kdf[..] {
+            "scrypt" => match (params.n, params.r, params.p) {
+                (Some(n), Some(r), Some(p)) => KeystoreKdf::Scrypt { n, r, p },
+                _ => return Err(Error::InvalidKeystore("missing scrypt parameters".to_string()))
+            },
+            "pbkdf2" => match (params.c, params.prf.as_ref().map(|p| &p[..])) {
+                (Some(c), Some("hmac-sha256")) => KeystoreKdf::Pbkdf2 { c },
+                _ => return Err(Error::InvalidKeystore("unsupported pbkdf2 parameters".to_string()))
+            },
+            kdf => return Err(Error::InvalidKeystore(format!("unsupported kdf {}", kdf)))
+        };
+
+        let derived = derive_key(password, params, kdf)?;
+        if !bool::from(mac(&derived, &crypto.ciphertext).ct_eq(&crypto.mac)) {
+            return Err(Error::KeystoreMacMismatch);
+        }
+        let mut key = [0u8; 16];
+        key.copy_from_slice(&derived[..16]);
+        let mut iv = [0u8; 16];
+        iv.copy_from_slice(&crypto.cipherparams.iv);
+        let private_key = aes128_ctr(&key, &iv, &crypto.ciphertext);
+        if private_key.len() != 32 {
+            return Err(Error::InvalidKeystore("ciphertext must be 32 bytes".to_string()));
+        }
+        Ok(H256::from(&private_key[..]))
+    }
+
+    /// Address stored alongside the key, if the keystore has one. It is not authenticated,
+    /// so it should only be used to pick a keystore and not trusted after decryption.
+    pub fn address(&self) -> Option<H160> {
+        let address = self.address.as_ref()?;
+        let bytes: Vec<u8> = address.strip_prefix("0x").unwrap_or(address).from_hex().ok()?;
+        if bytes.len() == 20 {
+            Some(H160::from(&bytes[..]))
+        } else {
+            None
+        }
+    }
+
+    /// Unique id of the keystore
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Parses keystore JSON
+    pub fn from_json(json: &str) -> Result<Keystore, Error> {
+        serde_json::from_str(json).map_err(|e| Error::InvalidKeystore(e.to_string()))
+    }
+
+    /// Serializes the keystore to JSON
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("keystores always serialize")
+    }
+
+    /// Reads a keystore file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Keystore, Error> {
+        let json = fs::read_to_string(path).map_err(|e| Error::Io(e.to_string()))?;
+        Keystore::from_json(&json)
+    }
+
+    /// Writes the keystore to a file, replacing any existing one
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        fs::write(path, self.to_json()).map_err(|e| Error::Io(e.to_string()))
+    }
+}
+
+fn derive_key(password: &str, params: &KdfParams, kdf: KeystoreKdf) -> Result<Vec<u8>, Error> {
+    if params.dklen != DERIVED_KEY_LENGTH {
+        return Err(Error::InvalidKeystore("derived key must be 32 bytes".to_string()));
+    }
+    let password = password.as_bytes();
+    match kdf {
+        KeystoreKdf::Scrypt { n, r, p } => {
+            // u128 holds both products for any u32 parameters
+            let work = n as u128 * r as u128 * p as u128;
+            let memory = 128 * r as u128 * (n as u128 + p as u128);
+            if n < 2 || !n.is_power_of_two() || r == 0 || p == 0 || work > MAX_SCRYPT_WORK
+                || memory > MAX_SCRYPT_MEMORY {
+                return Err(Error::InvalidKeystore("unsupported scrypt parameters".to_string()));
+            }
+            let scrypt_params = ScryptParams::new(n.trailing_zeros() as u8, r, p)
+                .map_err(|_| Error::InvalidKeystore("unsupported scrypt parameters".to_string()))?;
+            let mut derived = vec![0u8; DERIVED_KEY_LENGTH];
+            scrypt(password, &params.salt, &scrypt_params, &mut derived)
+                .map_err(|_| Error::InvalidKeystore("unsupported scrypt parameters".to_string()))?;
+            Ok(derived)
+        }
+        KeystoreKdf::Pbkdf2 { c } => {
+            if c == 0 {
+                return Err(Error::InvalidKeystore("pbkdf2 needs at least one iteration".to_string()));
+            }
+            if c > MAX_PBKDF2_ITERATIONS {
+                return Err(Error::InvalidKeystore("too many pbkdf2 iterations".to_string()));
+            }
+            let mut derived = vec![0u8; DERIVED_KEY_LENGTH];
+            pbkdf2_hmac::<Sha256>(password, &params.salt, c, &mut derived);
+            Ok(derived)
+        }
+    }
+}
+
+/// Encrypts or decrypts `data` with AES-128 in counter mode
+fn aes128_ctr(key: &[u8; 16], iv: &[u8; 16], data: &[u8]) -> Vec<u8> {
+    let mut out = data.to_vec();
+    Ctr128BE::<Aes128>::new(key.into(), iv.into()).apply_keystream(&mut out);
+    out
+}
+
+/// keccak256 of the second half of the derived key followed by the ciphertext
+fn mac(derived: &[u8], ciphertext: &[u8]) -> Vec<u8> {
+    let mut preimage = derived[16..32].to_vec();
+    preimage.extend_from_slice(ciphertext);
+    keccak256_hash(&preimage)
+}
+
+/// Formats random bytes as a version 4 UUID
+fn format_uuid(mut bytes: [u8; 16]) -> String {
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("{}-{}-{}-{}-{}", &hex[..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..])
+}
+
+/// Serde helpers for byte strings stored as unprefixed hex
+mod hex_bytes {
+    use rustc_hex::{FromHex, ToHex};
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&bytes.to_hex::<String>())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let hex = String::deserialize(deserializer)?;
+        hex.strip_prefix("0x").unwrap_or(&hex).from_hex().map_err(D::Error::custom)
+    }
+}
+
+mod test {
+
+    #[test]
+    fn test_decrypts_spec_vectors() {
+        use std::io::Read;
+        use std::fs::File;
+        use ethereum_types::*;
+        use keystore::Keystore;
+        use serde_json;
+
+        #[derive(Deserialize)]
+        struct Vector {
+            keystore: Keystore,
+            password: String,
+            private_key: H256
+        }
+
+        let mut file = File::open("./test/test_keystores.json").unwrap();
+        let mut f_string = String::new();
+        file.read_to_string(&mut f_string).unwrap();
+        let vectors: Vec<Vector> = serde_json::from_str(&f_string).unwrap();
+        for vector in vectors.into_iter() {
+            assert_eq!(vector.private_key, vector.keystore.decrypt(&vector.password).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_wrong_password() {
+        use ethereum_types::*;
+        use error::Error;
+        use keystore::{Keystore, KeystoreKdf};
+
+        let keystore = Keystore::encrypt(&H256::from(0x46), "password", KeystoreKdf::Pbkdf2 { c: 2 }).unwrap();
+        assert_eq!(Err(Error::KeystoreMacMismatch), keystore.decrypt("Password"));
+
+        let mut truncated = keystore.clone();
+        truncated.crypto.mac.pop();
+        assert_eq!(Err(Error::KeystoreMacMismatch), truncated.decrypt("password"));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        use ethereum_types::*;
+        use address::address_from_private_key;
+        use keystore::{Keystore, KeystoreKdf};
+
+        let private_key = H256::from(0x46);
+        for kdf in [KeystoreKdf::Scrypt { n: 16, r: 8, p: 1 }, KeystoreKdf::Pbkdf2 { c: 16 }] {
+            let keystore = Keystore::encrypt(&private_key, "testpassword", kdf).unwrap();
+            let keystore = Keystore::from_json(&keystore.to_json()).unwrap();
+            assert_eq!(private_key, keystore.decrypt("testpassword").unwrap());
+            assert_eq!(Some(address_from_private_key(&private_key).unwrap()), keystore.address());
+        }
+
+        let a = Keystore::encrypt(&private_key, "testpassword", KeystoreKdf::Pbkdf2 { c: 1 }).unwrap();
+        let b = Keystore::encrypt(&private_key, "testpassword", KeystoreKdf::Pbkdf2 { c: 1 }).unwrap();
+        assert!(a.id() != b.id());
+        assert_eq!(Some('4'), a.id().chars().nth(14));
+    }
+
+    #[test]
+    fn test_encrypts_deterministically() {
+        use ethereum_types::*;
+        use rustc_hex::FromHex;
+        use keystore::{Keystore, KeystoreKdf};
+
+        // the spec's pbkdf2 vector, reproduced from its salt and IV
+        let private_key = H256::from("0x7a28b5ba57c53603b0b07b56bba752f7784bf506fa95edc395f5cf6c7514fe9d");
+        let salt: Vec<u8> = "ae3cd4e7013836a3df6bd7241b12db061dbe2c6785853cce422d148a624ce0bd".from_hex().unwrap();
+        let mut iv = [0u8; 16];
+        iv.copy_from_slice(&"6087dab2f9fdbbfaddc31a909735c1e6".from_hex::<Vec<u8>>().unwrap());
+        let keystore = Keystore::encrypt_with(
+            &private_key, "testpassword", KeystoreKdf::Pbkdf2 { c: 262144 }, &salt, iv, [0u8; 16]
+        ).unwrap();
+        assert_eq!(
+            "5318b4d5bcd28de64ee5559e671353e16f075ecae9f99c7a79a38af5f869aa46".from_hex::<Vec<u8>>().unwrap(),
+            keystore.crypto.ciphertext
+        );
+        assert_eq!(
+            "517ead924a9d0dc3124507e3393d175ce3ff7c1e96529c6c555ce9e51205e9b2".from_hex::<Vec<u8>>().unwrap(),
+            keystore.crypto.mac
+        );
+    }
+
+    #[test]
+    fn test_rejects_malformed_keystores() {
+        use ethereum_types::*;
+        use error::Error;
+        use keystore::{Keystore, KeystoreKdf};
+
+        let keystore = Keystore::encrypt(&H256::from(0x46), "password", KeystoreKdf::Scrypt { n: 16, r: 1, p: 1 })
+            .unwrap();
+        let mut bad = keystore.clone();
+        bad.crypto.cipher = "aes-128-cbc".to_string();
+        assert_eq!(Err(Error::InvalidKeystore("unsupported cipher aes-128-cbc".to_string())), bad.decrypt("password"));
+        let mut bad = keystore.clone();
+        bad.crypto.kdfparams.n = Some(1 << 31);
+        assert!(bad.decrypt("password").is_err());
+        let mut bad = keystore.clone();
+        bad.crypto.kdfparams.n = Some(15);
+        assert!(bad.decrypt("password").is_err());
+        let mut bad = keystore.clone();
+        bad.crypto.kdfparams.p = Some(1 << 20);
+        assert_eq!(Err(Error::InvalidKeystore("unsupported scrypt parameters".to_string())), bad.decrypt("password"));
+        let mut bad = keystore.clone();
+        bad.crypto.kdfparams.n = Some(1 << 31);
+        bad.crypto.kdfparams.r = Some(u32::MAX);
+        bad.crypto.kdfparams.p = Some(u32::MAX);
+        assert_eq!(Err(Error::InvalidKeystore("unsupported scrypt parameters".to_string())), bad.decrypt("password"));
+        let mut bad = keystore.clone();
+        bad.crypto.kdfparams.dklen = 1 << 40;
+        assert_eq!(Err(Error::InvalidKeystore("derived key must be 32 bytes".to_string())), bad.decrypt("password"));
+        let mut bad = keystore.clone();
+        bad.crypto.kdfparams.dklen = 64;
+        assert!(bad.decrypt("password").is_err());
+        let mut bad = keystore.clone();
+        bad.version = 1;
+        assert!(bad.decrypt("password").is_err());
+        assert!(Keystore::from_json("{}").is_err());
+
+        let keystore = Keystore::encrypt(&H256::from(0x46), "password", KeystoreKdf::Pbkdf2 { c: 1 }).unwrap();
+        let mut bad = keystore.clone();
+        bad.crypto.kdfparams.c = Some(1 << 30);
+        assert_eq!(Err(Error::InvalidKeystore("too many pbkdf2 iterations".to_string())), bad.decrypt("password"));
+    }
+}
@@ -7,9 +7,63 @@ extern crate serde_json;
 extern crate tiny_keccak;
 extern crate secp256k1;
 extern crate rlp;
+extern crate rustc_hex;
+extern crate rand;
+extern crate c_kzg;
+extern crate aes;
+extern crate ctr;
+extern crate hmac;
+extern crate pbkdf2;
+extern crate scrypt;
+extern crate sha2;
+extern crate subtle;
 
+mod address;
+mod units;
+mod error;
+mod signature;
+mod message;
+mod eip712;
+mod abi;
+mod json_abi;
+mod contract;
+mod signer;
+mod async_signer;
+mod http;
+mod remote_signer;
+mod keystore;
+mod hd_wallet;
 mod raw_transaction;
 mod old_raw_transaction;
+mod access_list;
+mod access_list_transaction;
+mod eip1559_transaction;
+mod blob_transaction;
+mod set_code_transaction;
+mod typed_transaction;
+mod signed_transaction;
 
+pub use self::address::{Address, address_from_private_key, address_from_public_key, create_address, create2_address};
+pub use self::error::Error;
+pub use self::units::{Unit, deserialize_units, format_units, parse_units};
 pub use self::raw_transaction::RawTransaction;
 pub use self::old_raw_transaction::OldRawTransaction;
+pub use self::access_list::AccessList;
+pub use self::access_list_transaction::AccessListTransaction;
+pub use self::eip1559_transaction::Eip1559Transaction;
+pub use self::blob_transaction::{BlobSidecar, BlobTransaction, kzg_to_versioned_hash};
+pub use self::set_code_transaction::{Authorization, SetCodeTransaction, SignedAuthorization};
+pub use self::typed_transaction::TypedTransaction;
+pub use self::signed_transaction::SignedTransaction;
+pub use self::signature::Signature;
+pub use self::message::{hash_message, recover_message_signer, sign_message, verify_message};
+pub use self::eip712::{TypedData, TypedDataField};
+pub use self::abi::{FunctionSignature, ParamType, Revert, Token, decode, decode_revert, encode, encode_call, encode_typed};
+pub use self::json_abi::{Abi, AbiError, Constructor, Function, Param, StateMutability};
+pub use self::contract::{Contract, Deployer};
+pub use self::signer::{LocalSigner, Signer};
+pub use self::async_signer::{AsyncSigner, SignFuture, spawn_blocking};
+pub use self::remote_signer::{RemoteProtocol, RemoteSigner};
+pub use self::keystore::{Keystore, KeystoreKdf};
+pub use self::hd_wallet::{DerivationPath, ExtendedPrivateKey, HdWallet, HARDENED, mnemonic_to_entropy, mnemonic_to_seed};
+pub use secp256k1::key::PublicKey;
@@ -0,0 +1,86 @@
+use ethereum_types::{H160, H256};
+use error::Error;
+use signature::{ecdsa_sign, keccak256_hash, Signature};
+
+/// Prefix of EIP-191 version `0x45` ("personal") messages
+const MESSAGE_PREFIX: &str = "\x19Ethereum Signed Message:\n";
+
+/// Computes the EIP-191 hash `keccak256("\x19Ethereum Signed Message:\n" || len || message)`
+/// that `personal_sign` signs
+pub fn hash_message(message: &[u8]) -> H256 {
+    let mut preimage = format!("{}{}", MESSAGE_PREFIX, message.len()).into_bytes();
+    preimage.extend_from_slice(message);
+    H256::from(&keccak256_hash(&preimage)[..])
+}
+
+/// Signs a message as per EIP-191 and returns the 65 byte `r || s || v` signature
+pub fn sign_message(message: &[u8], private_key: &H256) -> Result<[u8; 65], Error> {
+    let sig = ecdsa_sign(&hash_message(message), &private_key.0)?;
+    Ok(sig.to_bytes())
+}
+
+/// Recovers the address that signed a message from its 65 byte `r || s || v` signature
+pub fn recover_message_signer(message: &[u8], signature: &[u8]) -> Result<H160, Error> {
+    Signature::from_bytes(signature)?.recover(&hash_message(message))
+}
+
+/// Checks that `signature` is a valid signature of `message` by `address`. Malformed
+/// signatures are reported as not matching.
+pub fn verify_message(message: &[u8], signature: &[u8], address: &H160) -> bool {
+    recover_message_signer(message, signature).ok() == Some(*address)
+}
+
+mod test {
+
+    #[test]
+    fn test_hash_message() {
+        use ethereum_types::*;
+        use message::hash_message;
+
+        // keccak256("\x19Ethereum Signed Message:\n11hello world")
+        assert_eq!(
+            H256::from("0xd9eba16ed0ecae432b71fe008c98cc872bb4cc214d3220a36f365326cf807d68"),
+            hash_message(b"hello world")
+        );
+    }
+
+    #[test]
+    fn test_sign_message() {
+        use ethereum_types::*;
+        use rustc_hex::ToHex;
+        use message::{recover_message_signer, sign_message, verify_message};
+        use address::address_from_private_key;
+
+        let private_key = H256::from("0x4646464646464646464646464646464646464646464646464646464646464646");
+        let address = address_from_private_key(&private_key).unwrap();
+        let signature = sign_message(b"hello world", &private_key).unwrap();
+        assert_eq!(
+            "78dc245805f4363bd546a771502385e03c40995b13fbab75de9258c6515db8d9\
+             2e831df32c6898bc590d0fb69945a72f6e31f1a70a325bf047ff5d557b1542ff1b",
+            signature.to_hex::<String>()
+        );
+        assert_eq!(address, recover_message_signer(b"hello world", &signature).unwrap());
+        assert!(verify_message(b"hello world", &signature, &address));
+        assert!(!verify_message(b"hello world!", &signature, &address));
+        assert!(!verify_message(b"hello world", &signature[..64], &address));
+
+        // v may also be given as the bare recovery id
+        let mut bare = signature;
+        bare[64] -= 27;
+        assert_eq!(address, recover_message_signer(b"hello world", &bare).unwrap());
+    }
+
+    #[test]
+    fn test_signer_sign_message() {
+        use ethereum_types::*;
+        use message::sign_message;
+        use signer::{LocalSigner, Signer};
+
+        let private_key = H256::from(0x46);
+        let signer = LocalSigner::new(&private_key).unwrap();
+        assert_eq!(
+            sign_message(b"login challenge", &private_key).unwrap().to_vec(),
+            signer.sign_message(b"login challenge").unwrap().to_vec()
+        );
+    }
+}
@@ -1,9 +1,6 @@
 use ethereum_types::{H160, H256, U256};
-use rlp::RlpStream;
-use tiny_keccak::keccak256;
-use secp256k1::key::SecretKey;
-use secp256k1::Message;
-use secp256k1::Secp256k1;
+use error::Error;
+use raw_transaction::RawTransaction;
 
 /// Description of a Transaction, pending or in the chain.
 #[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
@@ -24,78 +21,25 @@ pub struct OldRawTransaction {
 }
 
 impl OldRawTransaction {
-    /// Signs and returns the RLP-encoded transaction
-    pub fn sign(&self, private_key: &H256) -> Vec<u8> {
-        let hash = self.hash();
-        let sig = ecdsa_sign(&hash, &private_key.0);
-        println!("V {:?}",sig.v);
-        println!("R {:?}",sig.r);
-        println!("S {:?}",sig.s);
-        let mut R = sig.r;
-        let mut S = sig.s;
-        if R[0] == 0 {
-           R.remove(0);
-        }
-        if S[0] == 0 {
-           S.remove(0);
-        }
-        let mut tx = RlpStream::new(); 
-        tx.begin_unbounded_list();
-        self.encode(&mut tx);
-        tx.append(&sig.v); 
-        tx.append(&R); 
-        tx.append(&S); 
-        tx.complete_unbounded_list();
-        tx.out()
-    }
-
-    fn hash(&self) -> Vec<u8> {
-        let mut hash = RlpStream::new(); 
-        hash.begin_unbounded_list();
-        self.encode(&mut hash);
-        hash.complete_unbounded_list();
-        keccak256_hash(&hash.out())
-    }
-
-    fn encode(&self, s: &mut RlpStream) {
-        s.append(&self.nonce);
-        s.append(&self.gas_price);
-        s.append(&self.gas);
-        if let Some(ref t) = self.to {
-            s.append(t);
-        } else {
-            s.append(&vec![]);
-        }
-        s.append(&self.value);
-        s.append(&self.data);
+    /// Signs and returns the RLP-encoded transaction, without EIP-155 replay protection
+    pub fn sign(&self, private_key: &H256) -> Result<Vec<u8>, Error> {
+        RawTransaction::from(self.clone()).sign_with(private_key, None)
     }
 }
 
-fn keccak256_hash(bytes: &[u8]) -> Vec<u8> {
-    keccak256(bytes).into_iter().cloned().collect()
-}
-
-fn ecdsa_sign(hash: &[u8], private_key: &[u8]) -> EcdsaSig {
-    let s = Secp256k1::signing_only();
-    let msg = Message::from_slice(hash).unwrap();
-    let key = SecretKey::from_slice(&s, private_key).unwrap();
-    let (v, sig_bytes) = s.sign_recoverable(&msg, &key).serialize_compact(&s);
-
-    //println!("S {:?}", sig_bytes[32..64].to_vec());
-    // EIP155 implementation v: vec![v.to_i32() as u8 + CHAIN_ID * 2 + 35],
-    EcdsaSig {
-        v: vec![v.to_i32() as u8 + 27],
-        r: sig_bytes[0..32].to_vec(),
-        s: sig_bytes[32..64].to_vec(),
+impl From<OldRawTransaction> for RawTransaction {
+    fn from(tx: OldRawTransaction) -> RawTransaction {
+        RawTransaction {
+            nonce: tx.nonce,
+            to: tx.to,
+            value: tx.value,
+            gas_price: tx.gas_price,
+            gas: tx.gas,
+            data: tx.data
+        }
     }
 }
 
-pub struct EcdsaSig {
-    v: Vec<u8>,
-    r: Vec<u8>,
-    s: Vec<u8>
-}
-
 mod test {
 
     #[test]
@@ -103,7 +47,7 @@ mod test {
         use std::io::Read;
         use std::fs::File;
         use ethereum_types::*;
-        use raw_transaction::RawTransaction;
+        use old_raw_transaction::OldRawTransaction;
         use serde_json;
 
         #[derive(Deserialize)]
@@ -112,13 +56,13 @@ mod test {
             private_key: H256 
         }
 
-        let mut file = File::open("./test/test_txs.json").unwrap();
+        let mut file = File::open("./test/test_txs_old.json").unwrap();
         let mut f_string = String::new();
         file.read_to_string(&mut f_string).unwrap();
-        let txs: Vec<(RawTransaction, Signing)> = serde_json::from_str(&f_string).unwrap();
+        let txs: Vec<(OldRawTransaction, Signing)> = serde_json::from_str(&f_string).unwrap();
 
         for (tx, signed) in txs.into_iter() {
-            assert_eq!(signed.signed, tx.sign(&signed.private_key));
+            assert_eq!(signed.signed, tx.sign(&signed.private_key).unwrap());
         }
     }
 }
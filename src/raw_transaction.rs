@@ -1,9 +1,14 @@
 use ethereum_types::{H160, H256, U256};
 use rlp::RlpStream;
-use tiny_keccak::keccak256;
-use secp256k1::key::SecretKey;
-use secp256k1::Message;
-use secp256k1::Secp256k1;
+use serde::{Deserialize, Deserializer};
+use abi::{encode_call, Token};
+use address::Address;
+use async_signer::{AsyncSigner, SignFuture};
+use error::Error;
+use signature::{ecdsa_sign, keccak256_hash};
+use signed_transaction::SignedTransaction;
+use typed_transaction::TypedTransaction;
+use units::deserialize_units;
 
 /// Description of a Transaction, pending or in the chain.
 #[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
@@ -23,38 +28,124 @@ pub struct RawTransaction {
     pub data: Vec<u8>
 }
 
+/// A `RawTransaction` as deserialized in strict mode, with the recipient checksum checked
+#[derive(Deserialize)]
+struct StrictRawTransaction {
+    nonce: U256,
+    to: Option<Address>,
+    value: U256,
+    #[serde(rename = "gasPrice")]
+    gas_price: U256,
+    gas: U256,
+    data: Vec<u8>
+}
+
+/// A `RawTransaction` whose value and gas price may be written with units
+#[derive(Deserialize)]
+struct UnitsRawTransaction {
+    nonce: U256,
+    to: Option<H160>,
+    #[serde(deserialize_with = "deserialize_units")]
+    value: U256,
+    #[serde(rename = "gasPrice", deserialize_with = "deserialize_units")]
+    gas_price: U256,
+    gas: U256,
+    data: Vec<u8>
+}
+
 impl RawTransaction {
+    /// Deserializes a transaction in strict mode, rejecting a mixed-case `to` address whose
+    /// EIP-55 checksum is wrong. Use with `#[serde(deserialize_with = "...")]` or call it
+    /// on a deserializer directly.
+    pub fn deserialize_strict<'de, D: Deserializer<'de>>(deserializer: D) -> Result<RawTransaction, D::Error> {
+        let tx = StrictRawTransaction::deserialize(deserializer)?;
+        Ok(RawTransaction {
+            nonce: tx.nonce,
+            to: tx.to.map(H160::from),
+            value: tx.value,
+            gas_price: tx.gas_price,
+            gas: tx.gas,
+            data: tx.data
+        })
+    }
+
+    /// Deserializes a transaction whose `value` and `gasPrice` may also be written as
+    /// amounts such as `"1.5 ether"` or `"30 gwei"`. Use with
+    /// `#[serde(deserialize_with = "...")]` or call it on a deserializer directly.
+    pub fn deserialize_with_units<'de, D: Deserializer<'de>>(deserializer: D) -> Result<RawTransaction, D::Error> {
+        let tx = UnitsRawTransaction::deserialize(deserializer)?;
+        Ok(RawTransaction {
+            nonce: tx.nonce,
+            to: tx.to,
+            value: tx.value,
+            gas_price: tx.gas_price,
+            gas: tx.gas,
+            data: tx.data
+        })
+    }
+
+    /// Sets `data` to the calldata calling the function with the signature `signature`,
+    /// such as `transfer(address,uint256)`, with `args`
+    pub fn set_call_data(&mut self, signature: &str, args: &[Token]) -> Result<(), Error> {
+        self.data = encode_call(signature, args)?;
+        Ok(())
+    }
+
     /// Signs and returns the RLP-encoded transaction
-    pub fn sign(&self, private_key: &H256,chain_id : &u8) -> Vec<u8> {
-        let hash = self.hash(*chain_id);
-        let sig = ecdsa_sign(&hash, &private_key.0, &chain_id);
-        let mut R = sig.r;		
-	      let mut S = sig.s;		
-	      while R[0] == 0 {		
-	         R.remove(0);		
-	      }		
-	      while S[0] == 0 {		
-	         S.remove(0);		
-	      }
+    pub fn sign(&self, private_key: &H256, chain_id: &u64) -> Result<Vec<u8>, Error> {
+        self.sign_with(private_key, Some(*chain_id))
+    }
+
+    /// Signs the transaction and returns it together with its encoding, hash, signature and
+    /// sender
+    pub fn sign_transaction(&self, private_key: &H256, chain_id: &u64) -> Result<SignedTransaction, Error> {
+        TypedTransaction::Legacy(self.clone(), Some(*chain_id)).sign_transaction(private_key)
+    }
+
+    /// Signs the transaction with an asynchronous signer, replay protected as per EIP-155
+    pub fn sign_async<'a, S: AsyncSigner + ?Sized>(&self, signer: &'a S, chain_id: &u64)
+        -> SignFuture<'a, SignedTransaction> {
+        signer.sign_transaction(&TypedTransaction::Legacy(self.clone(), Some(*chain_id)))
+    }
+
+    /// Signs and returns the RLP-encoded transaction, replay protected as per EIP-155 when
+    /// `chain_id` is given
+    pub(crate) fn sign_with(&self, private_key: &H256, chain_id: Option<u64>) -> Result<Vec<u8>, Error> {
+        let sig = ecdsa_sign(&self.signing_hash(chain_id), &private_key.0)?;
+        let v = legacy_v(sig.recovery_id, chain_id)?;
+        Ok(self.encode_signed(v, &sig.r.into(), &sig.s.into()))
+    }
+
+    /// Returns the RLP-encoded transaction carrying the given signature
+    pub(crate) fn encode_signed(&self, v: u64, r: &U256, s: &U256) -> Vec<u8> {
         let mut tx = RlpStream::new(); 
         tx.begin_unbounded_list();
         self.encode(&mut tx);
-        tx.append(&sig.v); 
-        tx.append(&R); 
-        tx.append(&S); 
+        tx.append(&v); 
+        tx.append(r); 
+        tx.append(s); 
         tx.complete_unbounded_list();
         tx.out()
     }
 
-    fn hash(&self, chain_id: u8) -> Vec<u8> {
-        let mut hash = RlpStream::new(); 
-        hash.begin_unbounded_list();
-        self.encode(&mut hash);
-        hash.append(&mut vec![chain_id]);
-        hash.append(&mut U256::zero());
-        hash.append(&mut U256::zero());
-        hash.complete_unbounded_list();
-        keccak256_hash(&hash.out())
+    /// Returns the RLP payload the signature is made over, which includes the chain id as
+    /// per EIP-155 when one is given
+    pub fn encode_unsigned(&self, chain_id: Option<u64>) -> Vec<u8> {
+        let mut tx = RlpStream::new(); 
+        tx.begin_unbounded_list();
+        self.encode(&mut tx);
+        if let Some(chain_id) = chain_id {
+            tx.append(&chain_id);
+            tx.append(&U256::zero());
+            tx.append(&U256::zero());
+        }
+        tx.complete_unbounded_list();
+        tx.out()
+    }
+
+    /// Returns the hash the transaction signature is made over
+    pub fn signing_hash(&self, chain_id: Option<u64>) -> H256 {
+        H256::from(&keccak256_hash(&self.encode_unsigned(chain_id))[..])
     }
 
     fn encode(&self, s: &mut RlpStream) {
@@ -71,29 +162,16 @@ impl RawTransaction {
     }
 }
 
-fn keccak256_hash(bytes: &[u8]) -> Vec<u8> {
-    keccak256(bytes).into_iter().cloned().collect()
-}
-
-fn ecdsa_sign(hash: &[u8], private_key: &[u8], chain_id: &u8) -> EcdsaSig {
-    let s = Secp256k1::signing_only();
-    let msg = Message::from_slice(hash).unwrap();
-    let key = SecretKey::from_slice(&s, private_key).unwrap();
-    let (v, sig_bytes) = s.sign_recoverable(&msg, &key).serialize_compact(&s);
-
-    EcdsaSig {
-        v: vec![v.to_i32() as u8 + chain_id * 2 + 35],
-        r: sig_bytes[0..32].to_vec(),
-        s: sig_bytes[32..64].to_vec(),
+/// Computes the `v` value of a legacy signature, as per EIP-155 when a chain id is given
+pub(crate) fn legacy_v(recovery_id: u8, chain_id: Option<u64>) -> Result<u64, Error> {
+    match chain_id {
+        Some(chain_id) => chain_id.checked_mul(2)
+            .and_then(|v_base| v_base.checked_add(recovery_id as u64 + 35))
+            .ok_or(Error::InvalidChainId(chain_id)),
+        None => Ok(recovery_id as u64 + 27)
     }
 }
 
-pub struct EcdsaSig {
-    v: Vec<u8>,
-    r: Vec<u8>,
-    s: Vec<u8>
-}
-
 mod test {
 
     #[test]
@@ -114,9 +192,9 @@ mod test {
         let mut f_string = String::new();
         file.read_to_string(&mut f_string).unwrap();
         let txs: Vec<(RawTransaction, Signing)> = serde_json::from_str(&f_string).unwrap();
-        let chain_id = 0;
+        let chain_id = 1;
         for (tx, signed) in txs.into_iter() {
-            assert_eq!(signed.signed, tx.sign(&signed.private_key, &chain_id));
+            assert_eq!(signed.signed, tx.sign(&signed.private_key, &chain_id).unwrap());
         }
     }
 
@@ -140,7 +218,137 @@ mod test {
         let txs: Vec<(RawTransaction, Signing)> = serde_json::from_str(&f_string).unwrap();
         let chain_id = 3;
         for (tx, signed) in txs.into_iter() {
-            assert_eq!(signed.signed, tx.sign(&signed.private_key, &chain_id));
+            assert_eq!(signed.signed, tx.sign(&signed.private_key, &chain_id).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_signs_transaction_large_chain_id() {
+        use ethereum_types::*;
+        use raw_transaction::RawTransaction;
+        use rlp::Rlp;
+
+        let tx = RawTransaction {
+            nonce: U256::from(9),
+            to: Some(H160::from(0x35)),
+            value: U256::from(1000000000),
+            gas_price: U256::from(20000000000u64),
+            gas: U256::from(21000),
+            data: vec![]
+        };
+        let private_key = H256::from(0x46);
+        for chain_id in [137u64, 8453, 42161, 0xff_ffff_ffff].iter() {
+            let signed = tx.sign(&private_key, chain_id).unwrap();
+            let v: u64 = Rlp::new(&signed).val_at(6).unwrap();
+            assert!(v == chain_id * 2 + 35 || v == chain_id * 2 + 36);
         }
     }
+
+    #[test]
+    fn test_sign_rejects_invalid_private_key() {
+        use ethereum_types::*;
+        use raw_transaction::RawTransaction;
+        use error::Error;
+
+        let tx = RawTransaction::default();
+        let order = H256::from("0xfffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141");
+        assert_eq!(Err(Error::InvalidPrivateKey), tx.sign(&H256::zero(), &1));
+        assert_eq!(Err(Error::InvalidPrivateKey), tx.sign(&order, &1));
+    }
+
+    #[test]
+    fn test_sign_rejects_overflowing_chain_id() {
+        use ethereum_types::*;
+        use raw_transaction::RawTransaction;
+        use error::Error;
+
+        let tx = RawTransaction::default();
+        let private_key = H256::from(0x46);
+        let chain_id = u64::MAX / 2;
+        assert_eq!(Err(Error::InvalidChainId(chain_id)), tx.sign(&private_key, &chain_id));
+    }
+
+    #[test]
+    fn test_sign_transaction_returns_identifiers() {
+        use ethereum_types::*;
+        use raw_transaction::RawTransaction;
+        use typed_transaction::TypedTransaction;
+
+        // example from EIP-155
+        let tx = RawTransaction {
+            nonce: U256::from(9),
+            to: Some(H160::from("0x3535353535353535353535353535353535353535")),
+            value: U256::from(1000000000000000000u64),
+            gas_price: U256::from(20000000000u64),
+            gas: U256::from(21000),
+            data: vec![]
+        };
+        let private_key = H256::from("0x4646464646464646464646464646464646464646464646464646464646464646");
+        let signed = tx.sign_transaction(&private_key, &1).unwrap();
+        assert_eq!(tx.sign(&private_key, &1).unwrap(), signed.raw_bytes);
+        assert_eq!(TypedTransaction::Legacy(tx, Some(1)), signed.transaction);
+        assert_eq!(
+            H256::from("0x33469b22e9f636356c4160a87eb19df52b7412e8eac32a4a55ffe88ea8350788"),
+            signed.hash
+        );
+        assert_eq!(37, signed.v);
+        assert_eq!(H160::from("0x9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f"), signed.sender);
+    }
+
+    #[test]
+    fn test_deserializes_strict() {
+        use ethereum_types::*;
+        use serde_json;
+        use raw_transaction::RawTransaction;
+
+        let json = |to: &str| format!(
+            r#"{{"nonce":"0x9","gasPrice":"0x4a817c800","gas":"0x5208","to":{},"value":"0x0","data":[]}}"#, to
+        );
+        let strict = |json: &str| RawTransaction::deserialize_strict(&mut serde_json::Deserializer::from_str(json));
+
+        let checksummed = json("\"0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359\"");
+        let tx = strict(&checksummed).unwrap();
+        assert_eq!(Some(H160::from("0xfb6916095ca1df60bb79ce92ce3ea74c37c5d359")), tx.to);
+        assert_eq!(tx, serde_json::from_str(&checksummed).unwrap());
+        assert!(strict(&json("\"0xfb6916095ca1df60bb79ce92ce3ea74c37c5d359\"")).is_ok());
+        assert_eq!(None, strict(&json("null")).unwrap().to);
+
+        // one letter with the wrong case
+        let typo = json("\"0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5D359\"");
+        assert!(strict(&typo).is_err());
+        assert!(serde_json::from_str::<RawTransaction>(&typo).is_ok());
+
+        #[derive(Deserialize)]
+        struct Config {
+            #[serde(deserialize_with = "RawTransaction::deserialize_strict")]
+            tx: RawTransaction
+        }
+        let config: Config = serde_json::from_str(&format!("{{\"tx\":{}}}", checksummed)).unwrap();
+        assert_eq!(tx, config.tx);
+        assert!(serde_json::from_str::<Config>(&format!("{{\"tx\":{}}}", typo)).is_err());
+    }
+
+    #[test]
+    fn test_deserializes_with_units() {
+        use ethereum_types::*;
+        use serde_json;
+        use raw_transaction::RawTransaction;
+
+        let json = r#"{"nonce":"0x9","gasPrice":"20 gwei","gas":"0x5208",
+                       "to":"0x3535353535353535353535353535353535353535","value":"1 ether","data":[]}"#;
+        let tx = RawTransaction::deserialize_with_units(&mut serde_json::Deserializer::from_str(json)).unwrap();
+        assert_eq!(U256::from(20_000_000_000u64), tx.gas_price);
+        assert_eq!(U256::from(1_000_000_000_000_000_000u64), tx.value);
+
+        // the plain hex form is still accepted, and gives the same transaction
+        let hex = r#"{"nonce":"0x9","gasPrice":"0x4a817c800","gas":"0x5208",
+                      "to":"0x3535353535353535353535353535353535353535","value":"0xde0b6b3a7640000","data":[]}"#;
+        assert_eq!(tx, RawTransaction::deserialize_with_units(&mut serde_json::Deserializer::from_str(hex)).unwrap());
+        assert_eq!(tx, serde_json::from_str(hex).unwrap());
+
+        // units are opt-in
+        assert!(serde_json::from_str::<RawTransaction>(json).is_err());
+        let overflow = json.replace("1 ether", "1000000000000000000000000000000000000000000000000000000000000 ether");
+        assert!(RawTransaction::deserialize_with_units(&mut serde_json::Deserializer::from_str(&overflow)).is_err());
+    }
 }
@@ -0,0 +1,555 @@
+use std::fmt;
+use std::future;
+use std::time::Duration;
+use ethereum_types::{H160, H256, U256};
+use rustc_hex::{FromHex, ToHex};
+use serde_json::{self, Value};
+use access_list::AccessList;
+use async_signer::{spawn_blocking, AsyncSigner, SignFuture};
+use error::Error;
+use http;
+use signature::Signature;
+use signed_transaction::SignedTransaction;
+use signer::{check_transaction, finish_transaction, Signer};
+use typed_transaction::TypedTransaction;
+
+/// How long to wait on the remote signer unless configured otherwise
+const DEFAULT_TIMEOUT_SECS: u64 = 60;
+
+/// Protocol spoken by a remote signer
+#[derive(Debug, Clone, PartialEq)]
+pub enum RemoteProtocol {
+    /// The `eth_signTransaction` JSON-RPC method, as served by nodes and by the JSON-RPC
+    /// endpoint of Web3Signer
+    EthSignTransaction,
+    /// The `account_signTransaction` JSON-RPC method of Clef
+    Clef,
+    /// The Web3Signer `/api/v1/eth1/sign/{identifier}` REST endpoint, signing with the key
+    /// the given identifier refers to
+    Web3Signer(String)
+}
+
+/// Signer that asks an external signing service over HTTP to sign transactions for an
+/// account. Every signature it returns is checked to recover to that account.
+#[derive(Clone)]
+pub struct RemoteSigner {
+    url: String,
+    protocol: RemoteProtocol,
+    address: H160,
+    chain_id: Option<u64>,
+    timeout: Duration
+}
+
+/// Transaction as accepted by `eth_signTransaction` and `account_signTransaction`
+#[derive(Serialize)]
+struct TransactionArgs {
+    from: H160,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    to: Option<H160>,
+    gas: U256,
+    #[serde(rename = "gasPrice", skip_serializing_if = "Option::is_none")]
+    gas_price: Option<U256>,
+    #[serde(rename = "maxFeePerGas", skip_serializing_if = "Option::is_none")]
+    max_fee_per_gas: Option<U256>,
+    #[serde(rename = "maxPriorityFeePerGas", skip_serializing_if = "Option::is_none")]
+    max_priority_fee_per_gas: Option<U256>,
+    value: U256,
+    data: String,
+    nonce: U256,
+    #[serde(rename = "chainId", skip_serializing_if = "Option::is_none")]
+    chain_id: Option<U256>,
+    #[serde(rename = "accessList", skip_serializing_if = "Option::is_none")]
+    access_list: Option<AccessList>
+}
+
+#[derive(Serialize)]
+struct JsonRpcRequest<'a> {
+    jsonrpc: &'static str,
+    id: u64,
+    method: &'a str,
+    params: (TransactionArgs,)
+}
+
+#[derive(Deserialize)]
+struct JsonRpcResponse {
+    result: Option<Value>,
+    error: Option<JsonRpcError>
+}
+
+#[derive(Deserialize)]
+struct JsonRpcError {
+    code: i64,
+    message: String
+}
+
+#[derive(Serialize)]
+struct Web3SignerRequest {
+    data: String
+}
+
+impl RemoteSigner {
+    /// Creates a signer for `address` served at `url`, which must be a plain `http://` URL
+    pub fn new(url: &str, protocol: RemoteProtocol, address: H160) -> RemoteSigner {
+        RemoteSigner {
+            url: url.to_string(),
+            protocol,
+            address,
+            chain_id: None,
+            timeout: Duration::from_secs(DEFAULT_TIMEOUT_SECS)
+        }
+    }
+
+    /// Binds the signer to a single chain
+    pub fn with_chain_id(mut self, chain_id: u64) -> RemoteSigner {
+        self.chain_id = Some(chain_id);
+        self
+    }
+
+    /// Sets how long a whole request to the remote signer may take before giving up
+    pub fn with_timeout(mut self, timeout: Duration) -> RemoteSigner {
+        self.timeout = timeout;
+        self
+    }
+
+    fn sign_json_rpc(&self, method: &str, tx: &TypedTransaction) -> Result<SignedTransaction, Error> {
+        let request = JsonRpcRequest {
+            jsonrpc: "2.0",
+            id: 1,
+            method,
+            params: (self.transaction_args(tx)?,)
+        };
+        let body = serde_json::to_vec(&request).map_err(json_error)?;
+        let response = http::post(&self.url, "application/json", &body, self.timeout)?;
+        let response: JsonRpcResponse = serde_json::from_slice(&response.body).map_err(|e| {
+            if response.status == 200 {
+                json_error(e)
+            } else {
+                Error::Remote(format!("signer responded with HTTP status {}", response.status))
+            }
+        })?;
+        if let Some(error) = response.error {
+            return Err(Error::Remote(format!("signer error {}: {}", error.code, error.message)));
+        }
+        // nodes return the raw transaction on its own, Clef alongside the decoded transaction
+        let raw = match response.result {
+            Some(Value::String(ref raw)) => raw.clone(),
+            Some(Value::Object(ref result)) => match result.get("raw") {
+                Some(Value::String(raw)) => raw.clone(),
+                _ => return Err(Error::Remote("signer response has no raw transaction".to_string()))
+            },
+            _ => return Err(Error::Remote("signer response has no raw transaction".to_string()))
+        };
+
+        let signed = SignedTransaction::decode(&decode_hex(&raw)?)?;
+        if signed.sender != self.address {
+            return Err(Error::SignerAddressMismatch(signed.sender));
+        }
+        if signed.transaction != *tx {
+            return Err(Error::Remote("signer returned a different transaction than requested".to_string()));
+        }
+        Ok(signed)
+    }
+
+    fn sign_web3signer(&self, identifier: &str, tx: &TypedTransaction) -> Result<SignedTransaction, Error> {
+        // Web3Signer hashes the data itself, so it is sent the unsigned encoding
+        let request = Web3SignerRequest { data: encode_hex(&tx.encode_unsigned()) };
+        let body = serde_json::to_vec(&request).map_err(json_error)?;
+        let url = format!("{}/api/v1/eth1/sign/{}", self.url.trim_end_matches('/'), identifier);
+        let response = http::post(&url, "application/json", &body, self.timeout)?;
+        if response.status != 200 {
+            return Err(Error::Remote(format!("signer responded with HTTP status {}", response.status)));
+        }
+        let text = String::from_utf8(response.body)
+            .map_err(|_| Error::Remote("signer response is not text".to_string()))?;
+        let sig = Signature::from_bytes(&decode_hex(text.trim().trim_matches('"'))?)?;
+        finish_transaction(self.address, tx, &sig)
+    }
+
+    fn transaction_args(&self, tx: &TypedTransaction) -> Result<TransactionArgs, Error> {
+        let args = match *tx {
+            TypedTransaction::Legacy(ref tx, chain_id) => TransactionArgs {
+                from: self.address,
+                to: tx.to,
+                gas: tx.gas,
+                gas_price: Some(tx.gas_price),
+                max_fee_per_gas: None,
+                max_priority_fee_per_gas: None,
+                value: tx.value,
+                data: encode_hex(&tx.data),
+                nonce: tx.nonce,
+                chain_id: chain_id.map(U256::from),
+                access_list: None
+            },
+            TypedTransaction::AccessList(ref tx) => TransactionArgs {
+                from: self.address,
+                to: tx.to,
+                gas: tx.gas,
+                gas_price: Some(tx.gas_price),
+                max_fee_per_gas: None,
+                max_priority_fee_per_gas: None,
+                value: tx.value,
+                data: encode_hex(&tx.data),
+                nonce: tx.nonce,
+                chain_id: Some(U256::from(tx.chain_id)),
+                access_list: Some(tx.access_list.clone())
+            },
+            TypedTransaction::Eip1559(ref tx) => TransactionArgs {
+                from: self.address,
+                to: tx.to,
+                gas: tx.gas,
+                gas_price: None,
+                max_fee_per_gas: Some(tx.max_fee_per_gas),
+                max_priority_fee_per_gas: Some(tx.max_priority_fee_per_gas),
+                value: tx.value,
+                data: encode_hex(&tx.data),
+                nonce: tx.nonce,
+                chain_id: Some(U256::from(tx.chain_id)),
+                access_list: Some(tx.access_list.clone())
+            },
+            _ => return Err(Error::UnsupportedTransactionType(tx.tx_type()))
+        };
+        Ok(args)
+    }
+}
+
+impl Signer for RemoteSigner {
+    fn address(&self) -> H160 {
+        self.address
+    }
+
+    fn chain_id(&self) -> Option<u64> {
+        self.chain_id
+    }
+
+    /// Remote signers only sign whole transactions, never bare hashes
+    fn sign_hash(&self, _hash: &H256) -> Result<Signature, Error> {
+        Err(Error::Remote("remote signers only sign transactions".to_string()))
+    }
+
+    fn sign_transaction(&self, tx: &TypedTransaction) -> Result<SignedTransaction, Error> {
+        check_transaction(self.chain_id, tx)?;
+        match self.protocol {
+            RemoteProtocol::EthSignTransaction => self.sign_json_rpc("eth_signTransaction", tx),
+            RemoteProtocol::Clef => self.sign_json_rpc("account_signTransaction", tx),
+            RemoteProtocol::Web3Signer(ref identifier) => self.sign_web3signer(identifier, tx)
+        }
+    }
+}
+
+impl AsyncSigner for RemoteSigner {
+    fn address(&self) -> H160 {
+        self.address
+    }
+
+    fn chain_id(&self) -> Option<u64> {
+        self.chain_id
+    }
+
+    fn sign_hash<'a>(&'a self, hash: &H256) -> SignFuture<'a, Signature> {
+        Box::pin(future::ready(Signer::sign_hash(self, hash)))
+    }
+
+    /// Makes the request on a background thread, so the executor is not blocked while the
+    /// remote signer answers
+    fn sign_transaction<'a>(&'a self, tx: &TypedTransaction) -> SignFuture<'a, SignedTransaction> {
+        let (signer, tx) = (self.clone(), tx.clone());
+        spawn_blocking(move || Signer::sign_transaction(&signer, &tx))
+    }
+}
+
+impl fmt::Debug for RemoteSigner {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RemoteSigner")
+            .field("url", &self.url)
+            .field("protocol", &self.protocol)
+            .field("address", &self.address)
+            .field("chain_id", &self.chain_id)
+            .finish()
+    }
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    format!("0x{}", bytes.to_hex::<String>())
+}
+
+fn decode_hex(hex: &str) -> Result<Vec<u8>, Error> {
+    let digits = hex.strip_prefix("0x").unwrap_or(hex);
+    digits.from_hex().map_err(|_| Error::Remote(format!("signer returned invalid hex {}", hex)))
+}
+
+fn json_error(e: serde_json::Error) -> Error {
+    Error::Remote(format!("invalid signer response: {}", e))
+}
+
+mod test {
+
+    #[test]
+    fn test_signs_with_each_protocol() {
+        use std::io::{BufRead, BufReader, Read, Write};
+        use std::net::TcpListener;
+        use std::sync::Arc;
+        use std::sync::mpsc;
+        use std::task::{Context, Poll, Wake};
+        use std::thread::{self, JoinHandle, Thread};
+        use ethereum_types::*;
+        use rustc_hex::{FromHex, ToHex};
+        use serde_json::{self, Value};
+        use access_list_transaction::AccessListTransaction;
+        use address::address_from_private_key;
+        use async_signer::AsyncSigner;
+        use eip1559_transaction::Eip1559Transaction;
+        use raw_transaction::RawTransaction;
+        use remote_signer::{RemoteProtocol, RemoteSigner};
+        use signature::{ecdsa_sign, keccak256_hash};
+        use signer::Signer;
+        use typed_transaction::TypedTransaction;
+
+        // Serves a single request, answering with the body `respond` computes from the
+        // request path and body. Join the handle to surface failed assertions in `respond`.
+        fn serve_once<F>(respond: F) -> (String, JoinHandle<()>)
+            where F: FnOnce(&str, &str) -> String + Send + 'static {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let url = format!("http://{}", listener.local_addr().unwrap());
+            let handle = thread::spawn(move || {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let path = line.split(' ').nth(1).unwrap().to_string();
+                let mut length = 0;
+                while line != "\r\n" {
+                    line.clear();
+                    reader.read_line(&mut line).unwrap();
+                    if line.to_ascii_lowercase().starts_with("content-length:") {
+                        length = line[15..].trim().parse().unwrap();
+                    }
+                }
+                let mut body = vec![0u8; length];
+                reader.read_exact(&mut body).unwrap();
+                let response = respond(&path, &String::from_utf8(body).unwrap());
+                write!(
+                    &stream,
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                    response.len(),
+                    response
+                ).unwrap();
+            });
+            (url, handle)
+        }
+
+        fn hex(bytes: &[u8]) -> String {
+            format!("0x{}", bytes.to_hex::<String>())
+        }
+
+        struct ThreadWaker(Thread);
+
+        impl Wake for ThreadWaker {
+            fn wake(self: Arc<Self>) {
+                self.0.unpark();
+            }
+        }
+
+        // eth_signTransaction
+        let private_key = H256::from("0x4646464646464646464646464646464646464646464646464646464646464646");
+        let address = address_from_private_key(&private_key).unwrap();
+        let tx = RawTransaction {
+            nonce: U256::from(9),
+            to: Some(H160::from(0x35)),
+            value: U256::from(1000),
+            gas_price: U256::from(20000000000u64),
+            gas: U256::from(21000),
+            data: vec![0xca, 0xfe]
+        };
+        let raw = tx.sign(&private_key, &1).unwrap();
+        let response = format!(r#"{{"jsonrpc":"2.0","id":1,"result":"{}"}}"#, hex(&raw));
+        let (url, server) = serve_once(move |path, body| {
+            let request: Value = serde_json::from_str(body).unwrap();
+            assert_eq!("/", path);
+            assert_eq!("eth_signTransaction", request["method"]);
+            let args = &request["params"][0];
+            assert_eq!(format!("{:?}", address), args["from"].as_str().unwrap());
+            assert_eq!("0x0000000000000000000000000000000000000035", args["to"]);
+            assert_eq!("0x4a817c800", args["gasPrice"]);
+            assert_eq!("0x5208", args["gas"]);
+            assert_eq!("0x3e8", args["value"]);
+            assert_eq!("0xcafe", args["data"]);
+            assert_eq!("0x9", args["nonce"]);
+            assert_eq!("0x1", args["chainId"]);
+            assert_eq!(Value::Null, args["maxFeePerGas"]);
+            response
+        });
+
+        let signer = RemoteSigner::new(&url, RemoteProtocol::EthSignTransaction, address).with_chain_id(1);
+        let signed = Signer::sign_transaction(&signer, &TypedTransaction::Legacy(tx, Some(1))).unwrap();
+        server.join().unwrap();
+        assert_eq!(raw, signed.raw_bytes);
+        assert_eq!(address, signed.sender);
+
+        // account_signTransaction of Clef
+        let private_key = H256::from(0x46);
+        let address = address_from_private_key(&private_key).unwrap();
+        let tx = Eip1559Transaction {
+            chain_id: 5,
+            nonce: U256::from(1),
+            max_priority_fee_per_gas: U256::from(2),
+            max_fee_per_gas: U256::from(3),
+            gas: U256::from(21000),
+            to: Some(H160::from(0x35)),
+            ..Default::default()
+        };
+        let raw = tx.sign(&private_key).unwrap();
+        let response = format!(r#"{{"jsonrpc":"2.0","id":1,"result":{{"raw":"{}","tx":{{}}}}}}"#, hex(&raw));
+        let (url, server) = serve_once(move |_, body| {
+            let request: Value = serde_json::from_str(body).unwrap();
+            assert_eq!("account_signTransaction", request["method"]);
+            let args = &request["params"][0];
+            assert_eq!("0x3", args["maxFeePerGas"]);
+            assert_eq!("0x2", args["maxPriorityFeePerGas"]);
+            assert_eq!("0x5", args["chainId"]);
+            assert_eq!(Value::Array(vec![]), args["accessList"]);
+            assert_eq!(Value::Null, args["gasPrice"]);
+            response
+        });
+
+        let signer = RemoteSigner::new(&url, RemoteProtocol::Clef, address);
+        let signed = Signer::sign_transaction(&signer, &tx.into()).unwrap();
+        server.join().unwrap();
+        assert_eq!(raw, signed.raw_bytes);
+
+        // Web3Signer, which signs the unsigned encoding it is sent
+        let tx = AccessListTransaction { chain_id: 1, gas: U256::from(21000), ..Default::default() };
+        let (url, server) = serve_once(move |path, body| {
+            assert_eq!("/api/v1/eth1/sign/0xabcd", path);
+            let request: Value = serde_json::from_str(body).unwrap();
+            let data: Vec<u8> = request["data"].as_str().unwrap()[2..].from_hex().unwrap();
+            let sig = ecdsa_sign(&keccak256_hash(&data), &private_key.0).unwrap();
+            let mut bytes = sig.r.to_vec();
+            bytes.extend_from_slice(&sig.s);
+            bytes.push(sig.recovery_id + 27);
+            hex(&bytes)
+        });
+
+        let signer = RemoteSigner::new(&(url + "/"), RemoteProtocol::Web3Signer("0xabcd".to_string()), address);
+        let signed = Signer::sign_transaction(&signer, &tx.clone().into()).unwrap();
+        server.join().unwrap();
+        assert_eq!(tx.sign(&private_key).unwrap(), signed.raw_bytes);
+
+        // asynchronously, where the server holds its answer until the first poll has returned
+        let tx = RawTransaction::default();
+        let raw = tx.sign(&private_key, &1).unwrap();
+        let response = format!(r#"{{"jsonrpc":"2.0","id":1,"result":"{}"}}"#, hex(&raw));
+        let (answer, answer_rx) = mpsc::channel();
+        let (url, server) = serve_once(move |_, _| {
+            answer_rx.recv().unwrap();
+            response
+        });
+
+        let signer = RemoteSigner::new(&url, RemoteProtocol::EthSignTransaction, address);
+        let mut future = AsyncSigner::sign_transaction(&signer, &TypedTransaction::Legacy(tx, Some(1)));
+        let waker = Arc::new(ThreadWaker(thread::current())).into();
+        let mut cx = Context::from_waker(&waker);
+        assert!(future.as_mut().poll(&mut cx).is_pending());
+
+        answer.send(()).unwrap();
+        let signed = loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(signed) => break signed.unwrap(),
+                Poll::Pending => thread::park()
+            }
+        };
+        server.join().unwrap();
+        assert_eq!(raw, signed.raw_bytes);
+    }
+
+    #[test]
+    fn test_rejects_bad_responses() {
+        use std::io::{BufRead, BufReader, Read, Write};
+        use std::net::TcpListener;
+        use std::thread::{self, JoinHandle};
+        use ethereum_types::*;
+        use rustc_hex::ToHex;
+        use address::address_from_private_key;
+        use error::Error;
+        use raw_transaction::RawTransaction;
+        use remote_signer::{RemoteProtocol, RemoteSigner};
+        use signer::Signer;
+        use typed_transaction::TypedTransaction;
+
+        // Serves a single request, answering with the body `respond` computes from the
+        // request path and body. Join the handle to surface failed assertions in `respond`.
+        fn serve_once<F>(respond: F) -> (String, JoinHandle<()>)
+            where F: FnOnce(&str, &str) -> String + Send + 'static {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let url = format!("http://{}", listener.local_addr().unwrap());
+            let handle = thread::spawn(move || {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let path = line.split(' ').nth(1).unwrap().to_string();
+                let mut length = 0;
+                while line != "\r\n" {
+                    line.clear();
+                    reader.read_line(&mut line).unwrap();
+                    if line.to_ascii_lowercase().starts_with("content-length:") {
+                        length = line[15..].trim().parse().unwrap();
+                    }
+                }
+                let mut body = vec![0u8; length];
+                reader.read_exact(&mut body).unwrap();
+                let response = respond(&path, &String::from_utf8(body).unwrap());
+                write!(
+                    &stream,
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                    response.len(),
+                    response
+                ).unwrap();
+            });
+            (url, handle)
+        }
+
+        fn hex(bytes: &[u8]) -> String {
+            format!("0x{}", bytes.to_hex::<String>())
+        }
+
+        // signed by another account
+        let private_key = H256::from(0x46);
+        let address = address_from_private_key(&private_key).unwrap();
+        let tx = RawTransaction::default();
+        let other = H256::from(0x47);
+        let raw = tx.sign(&other, &1).unwrap();
+        let response = format!(r#"{{"jsonrpc":"2.0","id":1,"result":"{}"}}"#, hex(&raw));
+        let (url, server) = serve_once(move |_, _| response);
+
+        let signer = RemoteSigner::new(&url, RemoteProtocol::EthSignTransaction, address);
+        assert_eq!(
+            Err(Error::SignerAddressMismatch(address_from_private_key(&other).unwrap())),
+            signer.sign_transaction(&TypedTransaction::Legacy(tx.clone(), Some(1)))
+        );
+        server.join().unwrap();
+
+        // a different transaction than the one requested
+        let altered = RawTransaction { nonce: U256::from(7), ..Default::default() };
+        let raw = altered.sign(&private_key, &1).unwrap();
+        let response = format!(r#"{{"jsonrpc":"2.0","id":1,"result":"{}"}}"#, hex(&raw));
+        let (url, server) = serve_once(move |_, _| response);
+
+        let signer = RemoteSigner::new(&url, RemoteProtocol::EthSignTransaction, address);
+        match signer.sign_transaction(&TypedTransaction::Legacy(tx.clone(), Some(1))) {
+            Err(Error::Remote(_)) => {}
+            result => panic!("unexpected result {:?}", result)
+        }
+        server.join().unwrap();
+
+        // a JSON-RPC error
+        let (url, server) = serve_once(|_, _| {
+            r#"{"jsonrpc":"2.0","id":1,"error":{"code":-32000,"message":"Request denied"}}"#.to_string()
+        });
+        let signer = RemoteSigner::new(&url, RemoteProtocol::Clef, address);
+        assert_eq!(
+            Err(Error::Remote("signer error -32000: Request denied".to_string())),
+            signer.sign_transaction(&TypedTransaction::Legacy(tx, Some(1)))
+        );
+        server.join().unwrap();
+    }
+}
@@ -0,0 +1,294 @@
+use ethereum_types::{H160, H256, U256};
+use rlp::{Decodable, DecoderError, Encodable, Rlp, RlpStream};
+use access_list::AccessList;
+use error::Error;
+use address::address_from_public_key;
+use signature::{ecdsa_recover, ecdsa_sign, keccak256_hash};
+use typed_transaction::{envelope, TypedEnvelope};
+
+/// EIP-2718 type byte of a set-code transaction
+pub const TRANSACTION_TYPE: u8 = 0x04;
+/// Prefix of the message an authorization signature is made over
+const AUTHORIZATION_MAGIC: u8 = 0x05;
+
+/// Description of an EIP-7702 set-code transaction
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+pub struct SetCodeTransaction {
+    /// Chain the transaction is valid on
+    #[serde(rename = "chainId")]
+    pub chain_id: u64,
+    /// Nonce
+    pub nonce: U256,
+    /// Maximum fee per gas paid to the block producer
+    #[serde(rename = "maxPriorityFeePerGas")]
+    pub max_priority_fee_per_gas: U256,
+    /// Maximum total fee per gas, including the base fee
+    #[serde(rename = "maxFeePerGas")]
+    pub max_fee_per_gas: U256,
+    /// Gas amount
+    pub gas: U256,
+    /// Recipient (set-code transactions cannot create contracts)
+    pub to: H160,
+    /// Transfered value
+    pub value: U256,
+    /// Input data
+    pub data: Vec<u8>,
+    /// Addresses and storage keys the transaction plans to access
+    #[serde(rename = "accessList", default)]
+    pub access_list: AccessList,
+    /// Signed delegations to install on the authorizing accounts
+    #[serde(rename = "authorizationList")]
+    pub authorization_list: Vec<SignedAuthorization>
+}
+
+/// Permission for an account to execute the code deployed at `address`
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Authorization {
+    /// Chain the authorization is valid on, or zero for any chain
+    #[serde(rename = "chainId")]
+    pub chain_id: U256,
+    /// Address whose code the authorizing account delegates to
+    pub address: H160,
+    /// Nonce of the authorizing account
+    pub nonce: u64
+}
+
+/// An authorization together with the signature of the authorizing account
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+pub struct SignedAuthorization {
+    /// Chain the authorization is valid on, or zero for any chain
+    #[serde(rename = "chainId")]
+    pub chain_id: U256,
+    /// Address whose code the authorizing account delegates to
+    pub address: H160,
+    /// Nonce of the authorizing account
+    pub nonce: u64,
+    /// Parity of the y coordinate of the signature point
+    #[serde(rename = "yParity")]
+    pub y_parity: u8,
+    /// Signature r
+    pub r: U256,
+    /// Signature s
+    pub s: U256
+}
+
+impl SetCodeTransaction {
+    /// Signs and returns the typed transaction envelope `0x04 || rlp([...])`
+    pub fn sign(&self, private_key: &H256) -> Result<Vec<u8>, Error> {
+        TypedEnvelope::sign(self, private_key)
+    }
+
+    /// Returns the unsigned typed transaction envelope, which is what the signature is made over
+    pub fn encode_unsigned(&self) -> Vec<u8> {
+        TypedEnvelope::encode_unsigned(self)
+    }
+
+    /// Returns the hash the transaction signature is made over
+    pub fn signing_hash(&self) -> H256 {
+        TypedEnvelope::signing_hash(self)
+    }
+}
+
+impl TypedEnvelope for SetCodeTransaction {
+    const TRANSACTION_TYPE: u8 = TRANSACTION_TYPE;
+
+    fn append_fields(&self, s: &mut RlpStream) {
+        s.append(&self.chain_id);
+        s.append(&self.nonce);
+        s.append(&self.max_priority_fee_per_gas);
+        s.append(&self.max_fee_per_gas);
+        s.append(&self.gas);
+        s.append(&self.to);
+        s.append(&self.value);
+        s.append(&self.data);
+        s.append(&self.access_list);
+        s.append_list(&self.authorization_list);
+    }
+}
+
+impl Authorization {
+    /// Signs the authorization over `keccak(0x05 || rlp([chain_id, address, nonce]))`
+    pub fn sign(&self, private_key: &H256) -> Result<SignedAuthorization, Error> {
+        let sig = ecdsa_sign(&self.signing_hash(), &private_key.0)?;
+        Ok(SignedAuthorization {
+            chain_id: self.chain_id,
+            address: self.address,
+            nonce: self.nonce,
+            y_parity: sig.recovery_id,
+            r: sig.r.into(),
+            s: sig.s.into()
+        })
+    }
+
+    /// Returns the hash the authorization signature is made over
+    pub fn signing_hash(&self) -> H256 {
+        let mut hash = RlpStream::new();
+        hash.begin_list(3);
+        hash.append(&self.chain_id);
+        hash.append(&self.address);
+        hash.append(&self.nonce);
+        H256::from(&keccak256_hash(&envelope(AUTHORIZATION_MAGIC, hash))[..])
+    }
+}
+
+impl SignedAuthorization {
+    /// The authorization without its signature
+    pub fn authorization(&self) -> Authorization {
+        Authorization {
+            chain_id: self.chain_id,
+            address: self.address,
+            nonce: self.nonce
+        }
+    }
+
+    /// Recovers the address of the account that signed the authorization
+    pub fn recover_authority(&self) -> Result<H160, Error> {
+        let hash = self.authorization().signing_hash();
+        let r: [u8; 32] = self.r.into();
+        let s: [u8; 32] = self.s.into();
+        let public_key = ecdsa_recover(&hash, self.y_parity as u64, &r, &s)?;
+        Ok(address_from_public_key(&public_key))
+    }
+}
+
+impl Encodable for SignedAuthorization {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.begin_list(6);
+        s.append(&self.chain_id);
+        s.append(&self.address);
+        s.append(&self.nonce);
+        s.append(&self.y_parity);
+        s.append(&self.r);
+        s.append(&self.s);
+    }
+}
+
+impl Decodable for SignedAuthorization {
+    fn decode(rlp: &Rlp) -> Result<SignedAuthorization, DecoderError> {
+        if rlp.item_count()? != 6 {
+            return Err(DecoderError::RlpIncorrectListLen);
+        }
+        Ok(SignedAuthorization {
+            chain_id: rlp.val_at(0)?,
+            address: rlp.val_at(1)?,
+            nonce: rlp.val_at(2)?,
+            y_parity: rlp.val_at(3)?,
+            r: rlp.val_at(4)?,
+            s: rlp.val_at(5)?
+        })
+    }
+}
+
+mod test {
+
+    #[test]
+    fn test_matches_published_transactions() {
+        use std::io::Read;
+        use std::fs::File;
+        use ethereum_types::*;
+        use rustc_hex::FromHex;
+        use address::address_from_private_key;
+        use set_code_transaction::SetCodeTransaction;
+        use signed_transaction::SignedTransaction;
+        use typed_transaction::TypedTransaction;
+        use serde_json;
+
+        // transactions published with the test suite of alloy-rpc-types-eth 1.0.41, given
+        // either raw or as (y parity, r, s)
+        #[derive(Deserialize)]
+        struct Published {
+            description: String,
+            raw: Option<String>,
+            signature: Option<(u64, U256, U256)>,
+            hash: Option<H256>,
+            sender: Option<H160>,
+            signing_hash: Option<H256>
+        }
+
+        let mut file = File::open("./test/test_txs_eip7702.json").unwrap();
+        let mut f_string = String::new();
+        file.read_to_string(&mut f_string).unwrap();
+        let txs: Vec<(SetCodeTransaction, Published)> = serde_json::from_str(&f_string).unwrap();
+        for (tx, published) in txs.into_iter() {
+            let description = published.description;
+            if let Some(signing_hash) = published.signing_hash {
+                assert_eq!(signing_hash, tx.signing_hash(), "{}", description);
+            }
+            let tx = TypedTransaction::from(tx);
+            let raw = match (published.raw, published.signature) {
+                (Some(raw), _) => raw[2..].from_hex().unwrap(),
+                (None, Some((v, r, s))) => tx.encode_signed(v, &r, &s),
+                (None, None) => panic!("{} carries no signature", description)
+            };
+            let decoded = SignedTransaction::decode(&raw).unwrap();
+            assert_eq!(tx, decoded.transaction, "{}", description);
+            assert_eq!(raw, decoded.encode(), "{}", description);
+            if let Some(hash) = published.hash {
+                assert_eq!(hash, decoded.hash, "{}", description);
+            }
+            if let Some(sender) = published.sender {
+                assert_eq!(sender, decoded.sender, "{}", description);
+            }
+
+            let private_key = H256::from(0x46);
+            let signed = tx.sign_transaction(&private_key).unwrap();
+            assert_eq!(address_from_private_key(&private_key).unwrap(), signed.sender);
+            assert_eq!(signed, SignedTransaction::decode(&tx.sign(&private_key).unwrap()).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_encodes_signed_authorization() {
+        use ethereum_types::*;
+        use rlp;
+        use rustc_hex::FromHex;
+        use set_code_transaction::SignedAuthorization;
+
+        // test_encode_decode_signed_auth in alloy-eip7702 0.6.1
+        let signed = SignedAuthorization {
+            chain_id: U256::from(1),
+            address: H160::from(6),
+            nonce: 1,
+            y_parity: 0,
+            r: U256::from("48b55bfa915ac795c431978d8a6a992b628d557da5ff759b307d495a36649353"),
+            s: U256::from("efffd310ac743f371de3b9f7f9cb56c0b28ad43601b4ab949f53faa07bd2c804")
+        };
+        let expected: Vec<u8> = "f85a019400000000000000000000000000000000000000060180a048b55bfa915ac795c431978d8a6a992b628d557da5ff759b307d495a36649353a0efffd310ac743f371de3b9f7f9cb56c0b28ad43601b4ab949f53faa07bd2c804".from_hex().unwrap();
+        assert_eq!(expected, rlp::encode(&signed).to_vec());
+        assert_eq!(signed, rlp::decode::<SignedAuthorization>(&expected).unwrap());
+    }
+
+    #[test]
+    fn test_signs_and_recovers_authorization() {
+        use ethereum_types::*;
+        use set_code_transaction::Authorization;
+
+        let authorization = Authorization {
+            chain_id: U256::from(1),
+            address: H160::from("0x63c0c19a282a1b52b07dd5a65b58948a07dae32b"),
+            nonce: 7
+        };
+        let private_key = H256::from("0x4646464646464646464646464646464646464646464646464646464646464646");
+        let signed = authorization.sign(&private_key).unwrap();
+        assert_eq!(authorization, signed.authorization());
+        assert_eq!(
+            H160::from("0x9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f"),
+            signed.recover_authority().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_recover_authority_rejects_bad_signature() {
+        use ethereum_types::*;
+        use set_code_transaction::SignedAuthorization;
+        use error::Error;
+
+        let signed = SignedAuthorization {
+            y_parity: 2,
+            r: U256::from(1),
+            s: U256::from(1),
+            ..Default::default()
+        };
+        assert_eq!(Err(Error::InvalidSignature), signed.recover_authority());
+    }
+}
@@ -0,0 +1,102 @@
+use ethereum_types::{H160, H256};
+use tiny_keccak::keccak256;
+use secp256k1::key::{PublicKey, SecretKey};
+use secp256k1::{Message, RecoverableSignature, RecoveryId};
+use secp256k1::Secp256k1;
+use address::address_from_public_key;
+use error::Error;
+
+/// Half the order of the secp256k1 curve, the largest s EIP-2 allows
+const SECP256K1_HALF_ORDER: [u8; 32] = [
+    0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0x5d, 0x57, 0x6e, 0x73, 0x57, 0xa4, 0x50, 0x1d, 0xdf, 0xe9, 0x2f, 0x46, 0x68, 0x1b, 0x20, 0xa0
+];
+
+pub fn keccak256_hash(bytes: &[u8]) -> Vec<u8> {
+    keccak256(bytes).to_vec()
+}
+
+/// A recoverable secp256k1 signature over a 32 byte hash
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Signature {
+    /// Recovery id (0 or 1), the parity of the y coordinate of the signature point
+    pub recovery_id: u8,
+    /// Signature r
+    pub r: H256,
+    /// Signature s
+    pub s: H256
+}
+
+impl Signature {
+    /// Returns the 65 byte `r || s || v` form used by `personal_sign`, with v 27 or 28
+    pub fn to_bytes(&self) -> [u8; 65] {
+        let mut bytes = [0u8; 65];
+        bytes[..32].copy_from_slice(&self.r);
+        bytes[32..64].copy_from_slice(&self.s);
+        bytes[64] = 27 + self.recovery_id;
+        bytes
+    }
+
+    /// Parses a 65 byte `r || s || v` signature; v may be 0 or 1 as well as 27 or 28
+    pub fn from_bytes(bytes: &[u8]) -> Result<Signature, Error> {
+        if bytes.len() != 65 {
+            return Err(Error::InvalidSignature);
+        }
+        let recovery_id = match bytes[64] {
+            v @ 0..=1 => v,
+            v @ 27..=28 => v - 27,
+            _ => return Err(Error::InvalidSignature)
+        };
+        Ok(Signature {
+            recovery_id,
+            r: H256::from(&bytes[..32]),
+            s: H256::from(&bytes[32..64])
+        })
+    }
+
+    /// Recovers the address of the account that signed `hash`
+    pub fn recover(&self, hash: &H256) -> Result<H160, Error> {
+        let public_key = ecdsa_recover(hash, self.recovery_id as u64, &self.r, &self.s)?;
+        Ok(address_from_public_key(&public_key))
+    }
+}
+
+pub fn ecdsa_sign(hash: &[u8], private_key: &[u8]) -> Result<Signature, Error> {
+    let s = Secp256k1::signing_only();
+    let msg = Message::from_slice(hash)?;
+    let key = SecretKey::from_slice(&s, private_key)?;
+    let (v, sig_bytes) = s.sign_recoverable(&msg, &key).serialize_compact(&s);
+
+    Ok(Signature {
+        recovery_id: v.to_i32() as u8,
+        r: H256::from(&sig_bytes[0..32]),
+        s: H256::from(&sig_bytes[32..64]),
+    })
+}
+
+/// Recovers the public key that signed `hash`, given the bare recovery id (0 or 1). Fails
+/// for an s above half the curve order, so each signer has a single valid signature.
+pub fn ecdsa_recover(hash: &[u8], recovery_id: u64, r: &[u8], s: &[u8]) -> Result<PublicKey, Error> {
+    if recovery_id > 1 || r.len() != 32 || s.len() != 32 {
+        return Err(Error::InvalidSignature);
+    }
+    check_low_s(s)?;
+    let secp = Secp256k1::verification_only();
+    let msg = Message::from_slice(hash)?;
+    let mut sig_bytes = [0u8; 64];
+    sig_bytes[0..32].copy_from_slice(r);
+    sig_bytes[32..64].copy_from_slice(s);
+    let recovery_id = RecoveryId::from_i32(recovery_id as i32)?;
+    let sig = RecoverableSignature::from_compact(&secp, &sig_bytes, recovery_id)?;
+    Ok(secp.recover(&msg, &sig)?)
+}
+
+/// Checks that the big-endian signature value `s` is at most half the curve order. Since
+/// Homestead (EIP-2) nodes reject transactions whose s is above it, as `n - s` gives a
+/// second valid signature for the same hash.
+fn check_low_s(s: &[u8]) -> Result<(), Error> {
+    if s > &SECP256K1_HALF_ORDER[..] {
+        return Err(Error::HighS);
+    }
+    Ok(())
+}
@@ -0,0 +1,487 @@
+use ethereum_types::{H160, H256, U256};
+use rlp::{Decodable, DecoderError, Rlp};
+use access_list_transaction::{self, AccessListTransaction};
+use address::address_from_public_key;
+use blob_transaction::{self, BlobSidecar, BlobTransaction};
+use eip1559_transaction::{self, Eip1559Transaction};
+use error::Error;
+use raw_transaction::RawTransaction;
+use set_code_transaction::{self, SetCodeTransaction};
+use signature::{ecdsa_recover, keccak256_hash};
+use typed_transaction::TypedTransaction;
+
+/// A transaction together with its signature and the identifiers derived from it
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignedTransaction {
+    /// The transaction that was signed
+    pub transaction: TypedTransaction,
+    /// The signed encoding, as sent to `eth_sendRawTransaction`
+    pub raw_bytes: Vec<u8>,
+    /// Transaction hash; keccak of the canonical signed encoding, including the type byte
+    pub hash: H256,
+    /// Signature v; the EIP-155 value for legacy transactions, the y parity otherwise
+    pub v: u64,
+    /// Signature r
+    pub r: U256,
+    /// Signature s
+    pub s: U256,
+    /// Address of the account that signed the transaction
+    pub sender: H160
+}
+
+/// Transaction and signature values as read from an encoded transaction
+type Decoded = (TypedTransaction, u64, U256, U256);
+
+impl SignedTransaction {
+    /// Decodes a signed legacy transaction or EIP-2718 typed envelope. Blob transactions are
+    /// accepted both in their canonical and their network (sidecar carrying) form.
+    pub fn decode(bytes: &[u8]) -> Result<SignedTransaction, Error> {
+        let first = *bytes.first().ok_or(Error::Rlp(DecoderError::RlpIsTooShort))?;
+        let payload = if first >= 0xc0 { bytes } else { &bytes[1..] };
+        let rlp = Rlp::new(payload);
+        let total = rlp.payload_info()?.total();
+        if total < payload.len() {
+            return Err(Error::TrailingBytes);
+        } else if total > payload.len() {
+            return Err(Error::Rlp(DecoderError::RlpIsTooShort));
+        } else if !rlp.is_list() {
+            return Err(Error::Rlp(DecoderError::RlpExpectedToBeList));
+        }
+
+        let (transaction, v, r, sig_s) = match first {
+            0xc0..=0xff => decode_legacy(&rlp)?,
+            blob_transaction::TRANSACTION_TYPE if rlp.at(0)?.is_list() => decode_pooled_blob(&rlp)?,
+            tx_type => decode_typed(tx_type, &rlp)?
+        };
+        let canonical = transaction.encode_signed(v, &r, &sig_s);
+        let encoded = match transaction {
+            TypedTransaction::Blob(ref tx) if tx.sidecar.is_some() => tx.encode_pooled(v, &r, &sig_s)?,
+            _ => canonical.clone()
+        };
+        if encoded != bytes {
+            return Err(Error::NonCanonicalEncoding);
+        }
+
+        let mut signed = SignedTransaction {
+            transaction,
+            raw_bytes: bytes.to_vec(),
+            hash: H256::from(&keccak256_hash(&canonical)[..]),
+            v,
+            r,
+            s: sig_s,
+            sender: H160::zero()
+        };
+        signed.sender = signed.recover_sender()?;
+        Ok(signed)
+    }
+
+    /// Returns the canonical signed encoding of the transaction
+    pub fn encode(&self) -> Vec<u8> {
+        self.transaction.encode_signed(self.v, &self.r, &self.s)
+    }
+
+    /// Returns the hash the signature was made over
+    pub fn signing_hash(&self) -> H256 {
+        self.transaction.signing_hash()
+    }
+
+    /// Recovers the address of the account that signed the transaction
+    pub fn recover_sender(&self) -> Result<H160, Error> {
+        let recovery_id = match self.transaction {
+            TypedTransaction::Legacy(_, Some(chain_id)) => chain_id.checked_mul(2)
+                .and_then(|v_base| v_base.checked_add(35))
+                .and_then(|v_base| self.v.checked_sub(v_base)),
+            TypedTransaction::Legacy(_, None) => self.v.checked_sub(27),
+            _ => Some(self.v)
+        };
+        let recovery_id = recovery_id.ok_or(Error::InvalidSignature)?;
+        let r: [u8; 32] = self.r.into();
+        let s: [u8; 32] = self.s.into();
+        let public_key = ecdsa_recover(&self.signing_hash(), recovery_id, &r, &s)?;
+        Ok(address_from_public_key(&public_key))
+    }
+}
+
+fn decode_legacy(rlp: &Rlp) -> Result<Decoded, Error> {
+    expect_items(rlp, 9)?;
+    let v: u64 = rlp.val_at(6)?;
+    let chain_id = match v {
+        27 | 28 => None,
+        v if v >= 35 => Some((v - 35) / 2),
+        _ => return Err(Error::InvalidSignature)
+    };
+    let tx = RawTransaction {
+        nonce: rlp.val_at(0)?,
+        gas_price: rlp.val_at(1)?,
+        gas: rlp.val_at(2)?,
+        to: decode_to(&rlp.at(3)?)?,
+        value: rlp.val_at(4)?,
+        data: rlp.val_at(5)?
+    };
+    Ok((TypedTransaction::Legacy(tx, chain_id), v, rlp.val_at(7)?, rlp.val_at(8)?))
+}
+
+fn decode_typed(tx_type: u8, rlp: &Rlp) -> Result<Decoded, Error> {
+    let (transaction, fields) = match tx_type {
+        access_list_transaction::TRANSACTION_TYPE => {
+            expect_items(rlp, 11)?;
+            let tx = AccessListTransaction {
+                chain_id: rlp.val_at(0)?,
+                nonce: rlp.val_at(1)?,
+                gas_price: rlp.val_at(2)?,
+                gas: rlp.val_at(3)?,
+                to: decode_to(&rlp.at(4)?)?,
+                value: rlp.val_at(5)?,
+                data: rlp.val_at(6)?,
+                access_list: rlp.val_at(7)?
+            };
+            (TypedTransaction::AccessList(tx), 8)
+        }
+        eip1559_transaction::TRANSACTION_TYPE => {
+            expect_items(rlp, 12)?;
+            let tx = Eip1559Transaction {
+                chain_id: rlp.val_at(0)?,
+                nonce: rlp.val_at(1)?,
+                max_priority_fee_per_gas: rlp.val_at(2)?,
+                max_fee_per_gas: rlp.val_at(3)?,
+                gas: rlp.val_at(4)?,
+                to: decode_to(&rlp.at(5)?)?,
+                value: rlp.val_at(6)?,
+                data: rlp.val_at(7)?,
+                access_list: rlp.val_at(8)?
+            };
+            (TypedTransaction::Eip1559(tx), 9)
+        }
+        blob_transaction::TRANSACTION_TYPE => {
+            expect_items(rlp, 14)?;
+            let tx = BlobTransaction {
+                chain_id: rlp.val_at(0)?,
+                nonce: rlp.val_at(1)?,
+                max_priority_fee_per_gas: rlp.val_at(2)?,
+                max_fee_per_gas: rlp.val_at(3)?,
+                gas: rlp.val_at(4)?,
+                to: rlp.val_at(5)?,
+                value: rlp.val_at(6)?,
+                data: rlp.val_at(7)?,
+                access_list: rlp.val_at(8)?,
+                max_fee_per_blob_gas: rlp.val_at(9)?,
+                blob_versioned_hashes: rlp.list_at(10)?,
+                sidecar: None
+            };
+            (TypedTransaction::Blob(tx), 11)
+        }
+        set_code_transaction::TRANSACTION_TYPE => {
+            expect_items(rlp, 13)?;
+            let tx = SetCodeTransaction {
+                chain_id: rlp.val_at(0)?,
+                nonce: rlp.val_at(1)?,
+                max_priority_fee_per_gas: rlp.val_at(2)?,
+                max_fee_per_gas: rlp.val_at(3)?,
+                gas: rlp.val_at(4)?,
+                to: rlp.val_at(5)?,
+                value: rlp.val_at(6)?,
+                data: rlp.val_at(7)?,
+                access_list: rlp.val_at(8)?,
+                authorization_list: rlp.list_at(9)?
+            };
+            (TypedTransaction::SetCode(tx), 10)
+        }
+        tx_type => return Err(Error::UnsupportedTransactionType(tx_type))
+    };
+    let y_parity: u64 = rlp.val_at(fields)?;
+    if y_parity > 1 {
+        return Err(Error::InvalidSignature);
+    }
+    Ok((transaction, y_parity, rlp.val_at(fields + 1)?, rlp.val_at(fields + 2)?))
+}
+
+fn decode_pooled_blob(rlp: &Rlp) -> Result<Decoded, Error> {
+    expect_items(rlp, 4)?;
+    let mut decoded = decode_typed(blob_transaction::TRANSACTION_TYPE, &rlp.at(0)?)?;
+    if let TypedTransaction::Blob(ref mut tx) = decoded.0 {
+        tx.sidecar = Some(BlobSidecar {
+            blobs: rlp.list_at(1)?,
+            commitments: rlp.list_at(2)?,
+            proofs: rlp.list_at(3)?
+        });
+    }
+    Ok(decoded)
+}
+
+fn expect_items(rlp: &Rlp, count: usize) -> Result<(), Error> {
+    if rlp.item_count()? != count {
+        return Err(Error::Rlp(DecoderError::RlpIncorrectListLen));
+    }
+    Ok(())
+}
+
+/// An empty string in place of the recipient means contract creation
+fn decode_to(rlp: &Rlp) -> Result<Option<H160>, DecoderError> {
+    if rlp.is_data() && rlp.is_empty() {
+        Ok(None)
+    } else {
+        H160::decode(rlp).map(Some)
+    }
+}
+
+mod test {
+
+    #[test]
+    fn test_decodes_signed_transactions() {
+        use std::io::Read;
+        use std::fs::File;
+        use ethereum_types::*;
+        use serde_json;
+        use signed_transaction::SignedTransaction;
+
+        #[derive(Deserialize)]
+        struct Signing {
+            signed: Vec<u8>
+        }
+
+        let files = [
+            "./test/test_txs.json",
+            "./test/test_txs_ropsten.json",
+            "./test/test_txs_old.json"
+        ];
+        for path in files.iter() {
+            let mut file = File::open(path).unwrap();
+            let mut f_string = String::new();
+            file.read_to_string(&mut f_string).unwrap();
+            let txs: Vec<(serde_json::Value, Signing)> = serde_json::from_str(&f_string).unwrap();
+            for (_, signed) in txs.into_iter() {
+                let decoded = SignedTransaction::decode(&signed.signed).unwrap();
+                assert_eq!(signed.signed, decoded.encode());
+                assert!(decoded.r != U256::zero() && decoded.s != U256::zero());
+            }
+        }
+    }
+
+    #[test]
+    fn test_rejects_high_s() {
+        use std::io::Read;
+        use std::fs::File;
+        use ethereum_types::*;
+        use serde_json;
+        use eip1559_transaction::Eip1559Transaction;
+        use error::Error;
+        use signed_transaction::SignedTransaction;
+        use typed_transaction::TypedTransaction;
+
+        #[derive(Deserialize)]
+        struct Signing {
+            signed: Vec<u8>
+        }
+
+        let mut file = File::open("./test/test_txs.json").unwrap();
+        let mut f_string = String::new();
+        file.read_to_string(&mut f_string).unwrap();
+        let txs: Vec<(serde_json::Value, Signing)> = serde_json::from_str(&f_string).unwrap();
+        let mut signed: Vec<Vec<u8>> = txs.into_iter().map(|(_, signing)| signing.signed).collect();
+
+        let mut file = File::open("./test/test_txs_eip1559.json").unwrap();
+        let mut f_string = String::new();
+        file.read_to_string(&mut f_string).unwrap();
+        let txs: Vec<(Eip1559Transaction, serde_json::Value)> = serde_json::from_str(&f_string).unwrap();
+        for (tx, _) in txs.into_iter() {
+            signed.push(tx.sign(&H256::from(0x46)).unwrap());
+        }
+
+        let order = U256::from("fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141");
+        for signed in signed.into_iter() {
+            let decoded = SignedTransaction::decode(&signed).unwrap();
+            // n - s with the other recovery id is a valid signature for the same hash
+            let v = match decoded.transaction {
+                TypedTransaction::Legacy(..) if decoded.v % 2 == 0 => decoded.v - 1,
+                TypedTransaction::Legacy(..) => decoded.v + 1,
+                _ => decoded.v ^ 1
+            };
+            let malleated = decoded.transaction.encode_signed(v, &decoded.r, &(order - decoded.s));
+            assert_eq!(Err(Error::HighS), SignedTransaction::decode(&malleated));
+        }
+    }
+
+    #[test]
+    fn test_recovers_sender() {
+        use std::io::Read;
+        use std::fs::File;
+        use ethereum_types::*;
+        use serde_json;
+        use address::address_from_private_key;
+        use signed_transaction::SignedTransaction;
+
+        #[derive(Deserialize)]
+        struct Signing {
+            signed: Vec<u8>,
+            private_key: H256
+        }
+
+        let files = [
+            "./test/test_txs.json",
+            "./test/test_txs_ropsten.json",
+            "./test/test_txs_old.json"
+        ];
+        for path in files.iter() {
+            let mut file = File::open(path).unwrap();
+            let mut f_string = String::new();
+            file.read_to_string(&mut f_string).unwrap();
+            let txs: Vec<(serde_json::Value, Signing)> = serde_json::from_str(&f_string).unwrap();
+            for (_, signed) in txs.into_iter() {
+                let decoded = SignedTransaction::decode(&signed.signed).unwrap();
+                assert_eq!(
+                    address_from_private_key(&signed.private_key).unwrap(),
+                    decoded.recover_sender().unwrap()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_recover_sender_rejects_high_s() {
+        use ethereum_types::*;
+        use eip1559_transaction::Eip1559Transaction;
+        use error::Error;
+        use signature::Signature;
+        use typed_transaction::TypedTransaction;
+
+        let order = U256::from("fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141");
+        let tx = Eip1559Transaction { chain_id: 1, nonce: U256::from(1), ..Default::default() };
+        let mut signed = TypedTransaction::from(tx).sign_transaction(&H256::from(0x46)).unwrap();
+        // the malleated twin of the signature, which recovers the same public key
+        signed.s = order - signed.s;
+        signed.v ^= 1;
+        assert_eq!(Err(Error::HighS), signed.recover_sender());
+
+        let sig = Signature {
+            recovery_id: signed.v as u8,
+            r: H256::from(<[u8; 32]>::from(signed.r)),
+            s: H256::from(<[u8; 32]>::from(signed.s))
+        };
+        assert_eq!(Err(Error::HighS), sig.recover(&signed.signing_hash()));
+    }
+
+    #[test]
+    fn test_recover_sender_of_eip155_example() {
+        use ethereum_types::*;
+        use rustc_hex::FromHex;
+        use signed_transaction::SignedTransaction;
+
+        let raw: Vec<u8> = "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83"
+            .from_hex().unwrap();
+        let signed = SignedTransaction::decode(&raw).unwrap();
+        assert_eq!(
+            H160::from("0x9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f"),
+            signed.recover_sender().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_recover_sender_rejects_mismatched_v() {
+        use ethereum_types::*;
+        use error::Error;
+        use raw_transaction::RawTransaction;
+        use typed_transaction::TypedTransaction;
+
+        let tx = RawTransaction::default();
+        let mut signed = tx.sign_transaction(&H256::from(0x46), &1).unwrap();
+        signed.transaction = TypedTransaction::Legacy(tx, Some(5));
+        assert_eq!(Err(Error::InvalidSignature), signed.recover_sender());
+    }
+
+    #[test]
+    fn test_decodes_legacy_fields() {
+        use ethereum_types::*;
+        use raw_transaction::RawTransaction;
+        use signed_transaction::SignedTransaction;
+        use typed_transaction::TypedTransaction;
+
+        let tx = RawTransaction {
+            nonce: U256::from(9),
+            to: Some(H160::from("0x3535353535353535353535353535353535353535")),
+            value: U256::from(1000000000000000000u64),
+            gas_price: U256::from(20000000000u64),
+            gas: U256::from(21000),
+            data: vec![]
+        };
+        let private_key = H256::from("0x4646464646464646464646464646464646464646464646464646464646464646");
+        let decoded = SignedTransaction::decode(&tx.sign(&private_key, &1).unwrap()).unwrap();
+        assert_eq!(TypedTransaction::Legacy(tx, Some(1)), decoded.transaction);
+        assert_eq!(37, decoded.v);
+        assert_eq!(
+            U256::from("28ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276"),
+            decoded.r
+        );
+        assert_eq!(
+            U256::from("67cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83"),
+            decoded.s
+        );
+    }
+
+    #[test]
+    fn test_decodes_pooled_blob_transaction() {
+        use ethereum_types::*;
+        use blob_transaction::*;
+        use signed_transaction::SignedTransaction;
+        use typed_transaction::TypedTransaction;
+
+        let mut infinity = vec![0u8; 48];
+        infinity[0] = 0xc0;
+        let tx = BlobTransaction {
+            chain_id: 1,
+            to: H160::from(0x35),
+            blob_versioned_hashes: vec![kzg_to_versioned_hash(&infinity)],
+            sidecar: Some(BlobSidecar {
+                blobs: vec![vec![0u8; BYTES_PER_BLOB]],
+                commitments: vec![infinity.clone()],
+                proofs: vec![infinity.clone()]
+            }),
+            ..Default::default()
+        };
+        let pooled = tx.sign_pooled(&H256::from(0x46)).unwrap();
+        let canonical = tx.sign(&H256::from(0x46)).unwrap();
+        let decoded = SignedTransaction::decode(&pooled).unwrap();
+        assert_eq!(TypedTransaction::Blob(tx.clone()), decoded.transaction);
+        assert_eq!(canonical, decoded.encode());
+        assert_eq!(pooled, decoded.raw_bytes);
+        assert_eq!(SignedTransaction::decode(&canonical).unwrap().hash, decoded.hash);
+    }
+
+    #[test]
+    fn test_rejects_malformed_encodings() {
+        use ethereum_types::*;
+        use rlp::DecoderError;
+        use eip1559_transaction::Eip1559Transaction;
+        use error::Error;
+        use signed_transaction::SignedTransaction;
+
+        let tx = Eip1559Transaction { chain_id: 1, nonce: U256::from(1), ..Default::default() };
+        let signed = tx.sign(&H256::from(0x46)).unwrap();
+
+        let mut trailing = signed.clone();
+        trailing.push(0);
+        assert_eq!(Err(Error::TrailingBytes), SignedTransaction::decode(&trailing));
+
+        let mut truncated = signed.clone();
+        truncated.pop();
+        assert_eq!(Err(Error::Rlp(DecoderError::RlpIsTooShort)), SignedTransaction::decode(&truncated));
+
+        let mut unknown_type = signed.clone();
+        unknown_type[0] = 0x7f;
+        assert_eq!(Err(Error::UnsupportedTransactionType(0x7f)), SignedTransaction::decode(&unknown_type));
+
+        // nonce 1 written as a one byte string instead of the single byte 0x01
+        assert_eq!([0x02, 0xf8], signed[0..2]);
+        let mut non_canonical = vec![0x02, 0xf8, signed[2] + 1];
+        non_canonical.extend_from_slice(&signed[3..4]);
+        non_canonical.extend_from_slice(&[0x81, 0x01]);
+        non_canonical.extend_from_slice(&signed[5..]);
+        assert_eq!(Err(Error::Rlp(DecoderError::RlpInvalidIndirection)), SignedTransaction::decode(&non_canonical));
+
+        // the same nonce with the length of the string in long form
+        let mut long_form = vec![0x02, 0xf8, signed[2] + 2];
+        long_form.extend_from_slice(&signed[3..4]);
+        long_form.extend_from_slice(&[0xb8, 0x01, 0x01]);
+        long_form.extend_from_slice(&signed[5..]);
+        assert_eq!(Err(Error::NonCanonicalEncoding), SignedTransaction::decode(&long_form));
+    }
+}
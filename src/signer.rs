@@ -0,0 +1,296 @@
+use std::fmt;
+use ethereum_types::{H160, H256};
+use address::address_from_private_key;
+use eip712::TypedData;
+use error::Error;
+use keystore::{Keystore, KeystoreKdf};
+use message::hash_message;
+use raw_transaction::legacy_v;
+use signature::{ecdsa_sign, Signature};
+use signed_transaction::SignedTransaction;
+use typed_transaction::TypedTransaction;
+
+/// Something that can produce signatures for a single account, such as a key held in
+/// memory, a keystore or a remote signing service
+pub trait Signer {
+    /// Address of the account the signer signs for
+    fn address(&self) -> H160;
+
+    /// Chain the signer is bound to, if any. A bound signer refuses to sign transactions
+    /// for any other chain, including legacy transactions without replay protection.
+    fn chain_id(&self) -> Option<u64> {
+        None
+    }
+
+    /// Signs a 32 byte hash
+    fn sign_hash(&self, hash: &H256) -> Result<Signature, Error>;
+
+    /// Signs a message as per EIP-191 (`personal_sign`) and returns the 65 byte
+    /// `r || s || v` signature
+    fn sign_message(&self, message: &[u8]) -> Result<[u8; 65], Error> {
+        Ok(self.sign_hash(&hash_message(message))?.to_bytes())
+    }
+
+    /// Signs EIP-712 typed data and returns the 65 byte `r || s || v` signature
+    fn sign_typed_data(&self, data: &TypedData) -> Result<[u8; 65], Error> {
+        Ok(self.sign_hash(&data.signing_hash()?)?.to_bytes())
+    }
+
+    /// Signs a transaction and returns it together with its encoding, hash and signature
+    fn sign_transaction(&self, tx: &TypedTransaction) -> Result<SignedTransaction, Error> {
+        check_transaction(self.chain_id(), tx)?;
+        let sig = self.sign_hash(&tx.signing_hash())?;
+        finish_transaction(self.address(), tx, &sig)
+    }
+}
+
+/// Checks that `tx` is for the chain a signer is bound to, if it is bound to one, and that
+/// a blob transaction has a valid number of blobs and a sidecar matching them
+pub(crate) fn check_transaction(chain_id: Option<u64>, tx: &TypedTransaction) -> Result<(), Error> {
+    match chain_id {
+        Some(expected) if tx.chain_id() != Some(expected) => {
+            return Err(Error::ChainIdMismatch { expected, found: tx.chain_id() });
+        }
+        _ => {}
+    }
+    match *tx {
+        TypedTransaction::Blob(ref tx) => tx.validate(),
+        _ => Ok(())
+    }
+}
+
+/// Encodes `tx` with a signature obtained from a signer and checks that it was made by
+/// the account at `address`
+pub(crate) fn finish_transaction(address: H160, tx: &TypedTransaction, sig: &Signature)
+    -> Result<SignedTransaction, Error> {
+    let v = match *tx {
+        TypedTransaction::Legacy(_, chain_id) => legacy_v(sig.recovery_id, chain_id)?,
+        _ => sig.recovery_id as u64
+    };
+    let mut signed = SignedTransaction::decode(&tx.encode_signed(v, &sig.r.into(), &sig.s.into()))?;
+    if signed.sender != address {
+        return Err(Error::SignerAddressMismatch(signed.sender));
+    }
+    signed.transaction = tx.clone();
+    Ok(signed)
+}
+
+/// Signer holding a private key in memory
+#[derive(Clone)]
+pub struct LocalSigner {
+    private_key: H256,
+    address: H160,
+    chain_id: Option<u64>
+}
+
+impl LocalSigner {
+    /// Creates a signer for the account controlled by `private_key`
+    pub fn new(private_key: &H256) -> Result<LocalSigner, Error> {
+        Ok(LocalSigner {
+            private_key: *private_key,
+            address: address_from_private_key(private_key)?,
+            chain_id: None
+        })
+    }
+
+    /// Creates a signer from an encrypted keystore
+    pub fn from_keystore(keystore: &Keystore, password: &str) -> Result<LocalSigner, Error> {
+        LocalSigner::new(&keystore.decrypt(password)?)
+    }
+
+    /// Encrypts the signer's private key into a keystore
+    pub fn to_keystore(&self, password: &str, kdf: KeystoreKdf) -> Result<Keystore, Error> {
+        Keystore::encrypt(&self.private_key, password, kdf)
+    }
+
+    /// Binds the signer to a single chain
+    pub fn with_chain_id(mut self, chain_id: u64) -> LocalSigner {
+        self.chain_id = Some(chain_id);
+        self
+    }
+}
+
+impl Signer for LocalSigner {
+    fn address(&self) -> H160 {
+        self.address
+    }
+
+    fn chain_id(&self) -> Option<u64> {
+        self.chain_id
+    }
+
+    fn sign_hash(&self, hash: &H256) -> Result<Signature, Error> {
+        ecdsa_sign(hash, &self.private_key.0)
+    }
+}
+
+// never print the private key
+impl fmt::Debug for LocalSigner {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("LocalSigner")
+            .field("address", &self.address)
+            .field("chain_id", &self.chain_id)
+            .finish()
+    }
+}
+
+mod test {
+
+    #[test]
+    fn test_local_signer_matches_private_key_signing() {
+        use std::io::Read;
+        use std::fs::File;
+        use ethereum_types::*;
+        use eip1559_transaction::Eip1559Transaction;
+        use raw_transaction::RawTransaction;
+        use signer::{LocalSigner, Signer};
+        use typed_transaction::TypedTransaction;
+        use serde_json;
+
+        #[derive(Deserialize)]
+        struct Signing {
+            signed: Vec<u8>,
+            private_key: H256
+        }
+
+        let mut file = File::open("./test/test_txs.json").unwrap();
+        let mut f_string = String::new();
+        file.read_to_string(&mut f_string).unwrap();
+        let txs: Vec<(RawTransaction, Signing)> = serde_json::from_str(&f_string).unwrap();
+        for (tx, signed) in txs.into_iter() {
+            let signer = LocalSigner::new(&signed.private_key).unwrap().with_chain_id(1);
+            let signed_tx = signer.sign_transaction(&TypedTransaction::Legacy(tx, Some(1))).unwrap();
+            assert_eq!(signed.signed, signed_tx.raw_bytes);
+            assert_eq!(signer.address(), signed_tx.sender);
+        }
+
+        let mut file = File::open("./test/test_txs_eip1559.json").unwrap();
+        let mut f_string = String::new();
+        file.read_to_string(&mut f_string).unwrap();
+        let txs: Vec<(Eip1559Transaction, serde_json::Value)> = serde_json::from_str(&f_string).unwrap();
+        let private_key = H256::from(0x46);
+        let signer = LocalSigner::new(&private_key).unwrap();
+        for (tx, _) in txs.into_iter() {
+            assert_eq!(tx.sign(&private_key).unwrap(), signer.sign_transaction(&tx.into()).unwrap().raw_bytes);
+        }
+    }
+
+    #[test]
+    fn test_bound_signer_rejects_other_chains() {
+        use ethereum_types::*;
+        use eip1559_transaction::Eip1559Transaction;
+        use error::Error;
+        use raw_transaction::RawTransaction;
+        use signer::{LocalSigner, Signer};
+        use typed_transaction::TypedTransaction;
+
+        let signer = LocalSigner::new(&H256::from(0x46)).unwrap().with_chain_id(137);
+        let tx = Eip1559Transaction { chain_id: 1, ..Default::default() };
+        assert_eq!(
+            Err(Error::ChainIdMismatch { expected: 137, found: Some(1) }),
+            signer.sign_transaction(&tx.into())
+        );
+        let tx = TypedTransaction::Legacy(RawTransaction::default(), None);
+        assert_eq!(
+            Err(Error::ChainIdMismatch { expected: 137, found: None }),
+            signer.sign_transaction(&tx)
+        );
+    }
+
+    #[test]
+    fn test_rejects_invalid_blob_sidecar() {
+        use ethereum_types::*;
+        use blob_transaction::*;
+        use error::Error;
+        use signer::{LocalSigner, Signer};
+
+        let mut infinity = vec![0u8; 48];
+        infinity[0] = 0xc0;
+        let mut tx = BlobTransaction {
+            chain_id: 1,
+            to: H160::from(0x35),
+            blob_versioned_hashes: vec![kzg_to_versioned_hash(&infinity)],
+            sidecar: Some(BlobSidecar {
+                blobs: vec![vec![0u8; BYTES_PER_BLOB]],
+                commitments: vec![infinity.clone()],
+                proofs: vec![infinity.clone()]
+            }),
+            ..Default::default()
+        };
+        let signer = LocalSigner::new(&H256::from(0x46)).unwrap();
+        assert!(signer.sign_transaction(&tx.clone().into()).is_ok());
+
+        // the proof only holds for the all-zero blob
+        tx.sidecar.as_mut().unwrap().blobs[0][31] = 1;
+        assert_eq!(Err(Error::InvalidBlobProof(0)), signer.sign_transaction(&tx.clone().into()));
+        tx.blob_versioned_hashes = vec![H256::from(1)];
+        assert_eq!(Err(Error::BlobVersionedHashMismatch(0)), signer.sign_transaction(&tx.clone().into()));
+        tx.blob_versioned_hashes = vec![];
+        tx.sidecar = None;
+        assert_eq!(Err(Error::InvalidBlobCount(0)), signer.sign_transaction(&tx.into()));
+    }
+
+    #[test]
+    fn test_rejects_signature_from_other_account() {
+        use ethereum_types::*;
+        use error::Error;
+        use signature::Signature;
+        use signer::{LocalSigner, Signer};
+        use typed_transaction::TypedTransaction;
+        use raw_transaction::RawTransaction;
+
+        // claims one address but signs with another key
+        struct Impostor(LocalSigner, LocalSigner);
+        impl Signer for Impostor {
+            fn address(&self) -> H160 {
+                self.0.address()
+            }
+
+            fn sign_hash(&self, hash: &H256) -> Result<Signature, Error> {
+                self.1.sign_hash(hash)
+            }
+        }
+
+        let claimed = LocalSigner::new(&H256::from(0x46)).unwrap();
+        let actual = LocalSigner::new(&H256::from(0x47)).unwrap();
+        let actual_address = actual.address();
+        let impostor = Impostor(claimed, actual);
+        let tx = TypedTransaction::Legacy(RawTransaction::default(), Some(1));
+        assert_eq!(Err(Error::SignerAddressMismatch(actual_address)), impostor.sign_transaction(&tx));
+    }
+
+    #[test]
+    fn test_debug_hides_private_key() {
+        use ethereum_types::*;
+        use signer::LocalSigner;
+
+        let signer = LocalSigner::new(&H256::from(0x46)).unwrap();
+        assert!(!format!("{:?}", signer).contains("0000046"));
+    }
+
+    #[test]
+    fn test_keystore_signer() {
+        use std::env;
+        use std::fs;
+        use ethereum_types::*;
+        use keystore::{Keystore, KeystoreKdf};
+        use raw_transaction::RawTransaction;
+        use signer::{LocalSigner, Signer};
+        use typed_transaction::TypedTransaction;
+
+        let private_key = H256::from(0x46);
+        let signer = LocalSigner::new(&private_key).unwrap();
+        let path = env::temp_dir().join(format!("ethereum-tx-sign-keystore-{}.json", ::std::process::id()));
+        signer.to_keystore("secret", KeystoreKdf::Scrypt { n: 16, r: 8, p: 1 }).unwrap().save(&path).unwrap();
+        let keystore = Keystore::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let loaded = LocalSigner::from_keystore(&keystore, "secret").unwrap();
+        assert_eq!(signer.address(), loaded.address());
+        let tx = RawTransaction::default();
+        assert_eq!(
+            tx.sign(&private_key, &1).unwrap(),
+            loaded.sign_transaction(&TypedTransaction::Legacy(tx, Some(1))).unwrap().raw_bytes
+        );
+    }
+}
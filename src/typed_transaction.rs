@@ -0,0 +1,256 @@
+use ethereum_types::{H256, U256};
+use rlp::RlpStream;
+use access_list_transaction::{self, AccessListTransaction};
+use blob_transaction::{self, BlobTransaction};
+use eip1559_transaction::{self, Eip1559Transaction};
+use error::Error;
+use raw_transaction::RawTransaction;
+use set_code_transaction::{self, SetCodeTransaction};
+use signature::{ecdsa_sign, keccak256_hash};
+use signed_transaction::SignedTransaction;
+
+/// Type reported for legacy transactions, which have no type byte on the wire
+pub const LEGACY_TRANSACTION_TYPE: u8 = 0x00;
+
+/// Any of the transaction kinds the crate can sign
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypedTransaction {
+    /// Legacy transaction, replay protected as per EIP-155 when a chain id is given
+    Legacy(RawTransaction, Option<u64>),
+    /// EIP-2930 access-list transaction
+    AccessList(AccessListTransaction),
+    /// EIP-1559 dynamic-fee transaction
+    Eip1559(Eip1559Transaction),
+    /// EIP-4844 blob transaction
+    Blob(BlobTransaction),
+    /// EIP-7702 set-code transaction
+    SetCode(SetCodeTransaction),
+}
+
+impl TypedTransaction {
+    /// The EIP-2718 transaction type
+    pub fn tx_type(&self) -> u8 {
+        match *self {
+            TypedTransaction::Legacy(..) => LEGACY_TRANSACTION_TYPE,
+            TypedTransaction::AccessList(_) => access_list_transaction::TRANSACTION_TYPE,
+            TypedTransaction::Eip1559(_) => eip1559_transaction::TRANSACTION_TYPE,
+            TypedTransaction::Blob(_) => blob_transaction::TRANSACTION_TYPE,
+            TypedTransaction::SetCode(_) => set_code_transaction::TRANSACTION_TYPE,
+        }
+    }
+
+    /// The chain the transaction is valid on, or `None` for a legacy transaction without
+    /// replay protection
+    pub fn chain_id(&self) -> Option<u64> {
+        match *self {
+            TypedTransaction::Legacy(_, chain_id) => chain_id,
+            TypedTransaction::AccessList(ref tx) => Some(tx.chain_id),
+            TypedTransaction::Eip1559(ref tx) => Some(tx.chain_id),
+            TypedTransaction::Blob(ref tx) => Some(tx.chain_id),
+            TypedTransaction::SetCode(ref tx) => Some(tx.chain_id),
+        }
+    }
+
+    /// Signs and returns the encoded transaction, as it would be sent to `eth_sendRawTransaction`
+    pub fn sign(&self, private_key: &H256) -> Result<Vec<u8>, Error> {
+        match *self {
+            TypedTransaction::Legacy(ref tx, chain_id) => tx.sign_with(private_key, chain_id),
+            TypedTransaction::AccessList(ref tx) => tx.sign(private_key),
+            TypedTransaction::Eip1559(ref tx) => tx.sign(private_key),
+            TypedTransaction::Blob(ref tx) => tx.sign(private_key),
+            TypedTransaction::SetCode(ref tx) => tx.sign(private_key),
+        }
+    }
+
+    /// Signs the transaction and returns it together with its encoding, hash, signature and
+    /// sender. For blob transactions `raw_bytes` is the canonical encoding without sidecar.
+    pub fn sign_transaction(&self, private_key: &H256) -> Result<SignedTransaction, Error> {
+        let mut signed = SignedTransaction::decode(&self.sign(private_key)?)?;
+        signed.transaction = self.clone();
+        Ok(signed)
+    }
+
+    /// Returns the encoded transaction carrying the given signature values
+    pub(crate) fn encode_signed(&self, v: u64, r: &U256, s: &U256) -> Vec<u8> {
+        match *self {
+            TypedTransaction::Legacy(ref tx, _) => tx.encode_signed(v, r, s),
+            TypedTransaction::AccessList(ref tx) => tx.encode_signed(v, r, s),
+            TypedTransaction::Eip1559(ref tx) => tx.encode_signed(v, r, s),
+            TypedTransaction::Blob(ref tx) => tx.encode_signed(v, r, s),
+            TypedTransaction::SetCode(ref tx) => tx.encode_signed(v, r, s),
+        }
+    }
+
+    /// Returns the unsigned encoding the signature is made over
+    pub fn encode_unsigned(&self) -> Vec<u8> {
+        match *self {
+            TypedTransaction::Legacy(ref tx, chain_id) => tx.encode_unsigned(chain_id),
+            TypedTransaction::AccessList(ref tx) => tx.encode_unsigned(),
+            TypedTransaction::Eip1559(ref tx) => tx.encode_unsigned(),
+            TypedTransaction::Blob(ref tx) => tx.encode_unsigned(),
+            TypedTransaction::SetCode(ref tx) => tx.encode_unsigned(),
+        }
+    }
+
+    /// Returns the hash the transaction signature is made over
+    pub fn signing_hash(&self) -> H256 {
+        match *self {
+            TypedTransaction::Legacy(ref tx, chain_id) => tx.signing_hash(chain_id),
+            TypedTransaction::AccessList(ref tx) => tx.signing_hash(),
+            TypedTransaction::Eip1559(ref tx) => tx.signing_hash(),
+            TypedTransaction::Blob(ref tx) => tx.signing_hash(),
+            TypedTransaction::SetCode(ref tx) => tx.signing_hash(),
+        }
+    }
+}
+
+impl From<AccessListTransaction> for TypedTransaction {
+    fn from(tx: AccessListTransaction) -> TypedTransaction {
+        TypedTransaction::AccessList(tx)
+    }
+}
+
+impl From<Eip1559Transaction> for TypedTransaction {
+    fn from(tx: Eip1559Transaction) -> TypedTransaction {
+        TypedTransaction::Eip1559(tx)
+    }
+}
+
+impl From<BlobTransaction> for TypedTransaction {
+    fn from(tx: BlobTransaction) -> TypedTransaction {
+        TypedTransaction::Blob(tx)
+    }
+}
+
+impl From<SetCodeTransaction> for TypedTransaction {
+    fn from(tx: SetCodeTransaction) -> TypedTransaction {
+        TypedTransaction::SetCode(tx)
+    }
+}
+
+/// Wraps an RLP payload into an EIP-2718 envelope `prefix || payload`
+pub(crate) fn envelope(prefix: u8, payload: RlpStream) -> Vec<u8> {
+    let mut bytes = vec![prefix];
+    bytes.extend(payload.out());
+    bytes
+}
+
+/// Encoding and signing of the EIP-2718 typed transactions, which differ only in their type
+/// byte and fields
+pub(crate) trait TypedEnvelope {
+    /// EIP-2718 type byte of the transaction
+    const TRANSACTION_TYPE: u8;
+
+    /// Appends the fields of the transaction, without signature, to an open RLP list
+    fn append_fields(&self, s: &mut RlpStream);
+
+    /// Appends the transaction fields followed by the signature values as one RLP list
+    fn append_signed(&self, s: &mut RlpStream, y_parity: u64, r: &U256, sig_s: &U256) {
+        s.begin_unbounded_list();
+        self.append_fields(s);
+        s.append(&y_parity);
+        s.append(r);
+        s.append(sig_s);
+        s.complete_unbounded_list();
+    }
+
+    /// Returns the typed transaction envelope carrying the given signature
+    fn encode_signed(&self, y_parity: u64, r: &U256, s: &U256) -> Vec<u8> {
+        let mut tx = RlpStream::new();
+        self.append_signed(&mut tx, y_parity, r, s);
+        envelope(Self::TRANSACTION_TYPE, tx)
+    }
+
+    /// Returns the unsigned typed transaction envelope, which is what the signature is made over
+    fn encode_unsigned(&self) -> Vec<u8> {
+        let mut tx = RlpStream::new();
+        tx.begin_unbounded_list();
+        self.append_fields(&mut tx);
+        tx.complete_unbounded_list();
+        envelope(Self::TRANSACTION_TYPE, tx)
+    }
+
+    /// Returns the hash the transaction signature is made over
+    fn signing_hash(&self) -> H256 {
+        H256::from(&keccak256_hash(&self.encode_unsigned())[..])
+    }
+
+    /// Signs and returns the typed transaction envelope
+    fn sign(&self, private_key: &H256) -> Result<Vec<u8>, Error> {
+        let sig = ecdsa_sign(&self.signing_hash(), &private_key.0)?;
+        Ok(self.encode_signed(sig.recovery_id as u64, &sig.r.into(), &sig.s.into()))
+    }
+}
+
+mod test {
+
+    #[test]
+    fn test_signs_mixed_transactions() {
+        use std::io::Read;
+        use std::fs::File;
+        use ethereum_types::*;
+        use eip1559_transaction::Eip1559Transaction;
+        use old_raw_transaction::OldRawTransaction;
+        use raw_transaction::RawTransaction;
+        use typed_transaction::TypedTransaction;
+        use serde_json;
+
+        #[derive(Deserialize)]
+        struct Signing {
+            signed: Vec<u8>,
+            private_key: H256
+        }
+
+        fn load<T: ::serde::de::DeserializeOwned, S: ::serde::de::DeserializeOwned>(path: &str) -> Vec<(T, S)> {
+            let mut file = File::open(path).unwrap();
+            let mut f_string = String::new();
+            file.read_to_string(&mut f_string).unwrap();
+            serde_json::from_str(&f_string).unwrap()
+        }
+
+        let mut queue: Vec<(TypedTransaction, Signing)> = vec![];
+        for (tx, signed) in load::<RawTransaction, Signing>("./test/test_txs.json") {
+            queue.push((TypedTransaction::Legacy(tx, Some(1)), signed));
+        }
+        for (tx, signed) in load::<OldRawTransaction, Signing>("./test/test_txs_old.json") {
+            queue.push((TypedTransaction::Legacy(tx.into(), None), signed));
+        }
+        let private_key = H256::from(0x46);
+        for (tx, _) in load::<Eip1559Transaction, serde_json::Value>("./test/test_txs_eip1559.json") {
+            let signed = tx.sign(&private_key).unwrap();
+            queue.push((tx.into(), Signing { signed, private_key }));
+        }
+
+        for (tx, signed) in queue.into_iter() {
+            assert_eq!(signed.signed, tx.sign(&signed.private_key).unwrap());
+            if tx.tx_type() == 0 {
+                assert!(tx.encode_unsigned()[0] >= 0xc0);
+            } else {
+                assert_eq!(tx.tx_type(), tx.encode_unsigned()[0]);
+                assert_eq!(tx.tx_type(), signed.signed[0]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_legacy_signing_hash() {
+        use ethereum_types::*;
+        use raw_transaction::RawTransaction;
+        use typed_transaction::TypedTransaction;
+
+        // example from EIP-155
+        let tx = RawTransaction {
+            nonce: U256::from(9),
+            to: Some(H160::from("0x3535353535353535353535353535353535353535")),
+            value: U256::from(1000000000000000000u64),
+            gas_price: U256::from(20000000000u64),
+            gas: U256::from(21000),
+            data: vec![]
+        };
+        let tx = TypedTransaction::Legacy(tx, Some(1));
+        assert_eq!(
+            H256::from("0xdaf5a779ae972f972197303d7b574746c7ef83eadac0f2791ad23db92e4c8e53"),
+            tx.signing_hash()
+        );
+    }
+}
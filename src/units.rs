@@ -0,0 +1,241 @@
+use std::fmt;
+use std::str::FromStr;
+use ethereum_types::U256;
+use serde::de::{self, Deserializer, Visitor};
+use error::Error;
+
+/// A denomination of ether
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    Wei,
+    Kwei,
+    Mwei,
+    Gwei,
+    Szabo,
+    Finney,
+    Ether
+}
+
+impl Unit {
+    /// Returns the number of decimals of the unit, e.g. 9 for gwei
+    pub fn decimals(&self) -> usize {
+        match *self {
+            Unit::Wei => 0,
+            Unit::Kwei => 3,
+            Unit::Mwei => 6,
+            Unit::Gwei => 9,
+            Unit::Szabo => 12,
+            Unit::Finney => 15,
+            Unit::Ether => 18
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match *self {
+            Unit::Wei => "wei",
+            Unit::Kwei => "kwei",
+            Unit::Mwei => "mwei",
+            Unit::Gwei => "gwei",
+            Unit::Szabo => "szabo",
+            Unit::Finney => "finney",
+            Unit::Ether => "ether"
+        }
+    }
+}
+
+impl FromStr for Unit {
+    type Err = Error;
+
+    /// Parses a unit name, ignoring case and accepting the common aliases such as `shannon`
+    fn from_str(s: &str) -> Result<Unit, Error> {
+        match s.to_ascii_lowercase().as_str() {
+            "wei" => Ok(Unit::Wei),
+            "kwei" | "babbage" => Ok(Unit::Kwei),
+            "mwei" | "lovelace" => Ok(Unit::Mwei),
+            "gwei" | "shannon" => Ok(Unit::Gwei),
+            "szabo" | "microether" => Ok(Unit::Szabo),
+            "finney" | "milliether" => Ok(Unit::Finney),
+            "ether" | "eth" => Ok(Unit::Ether),
+            _ => Err(Error::InvalidAmount(format!("unknown unit {}", s)))
+        }
+    }
+}
+
+impl fmt::Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Parses an amount such as `1.5 ether` or `30 gwei` into wei. Amounts without a unit are
+/// taken as wei, either decimal or `0x` prefixed hex.
+pub fn parse_units(s: &str) -> Result<U256, Error> {
+    let s = s.trim();
+    if let Some(hex) = s.strip_prefix("0x") {
+        if hex.is_empty() {
+            return Err(Error::InvalidAmount(s.to_string()));
+        }
+        if hex.trim_start_matches('0').len() > 64 {
+            return Err(Error::AmountOverflow(s.to_string()));
+        }
+        return U256::from_str(hex.trim_start_matches('0')).map_err(|_| Error::InvalidAmount(s.to_string()));
+    }
+    let split = s.find(|c: char| c.is_ascii_alphabetic()).unwrap_or(s.len());
+    let (number, unit) = (s[..split].trim_end(), &s[split..]);
+    let unit = if unit.is_empty() { Unit::Wei } else { unit.parse()? };
+    parse_decimal(number, unit.decimals()).map_err(|e| match e {
+        Error::AmountOverflow(_) => Error::AmountOverflow(s.to_string()),
+        _ => Error::InvalidAmount(s.to_string())
+    })
+}
+
+/// Formats an amount of wei in `unit`, e.g. `1.5 ether`, with no trailing zeros
+pub fn format_units(value: &U256, unit: Unit) -> String {
+    let scale = U256::exp10(unit.decimals());
+    let (whole, fraction) = (*value / scale, *value % scale);
+    if fraction.is_zero() {
+        return format!("{} {}", whole, unit);
+    }
+    let fraction = format!("{:0>width$}", fraction.to_string(), width = unit.decimals());
+    format!("{}.{} {}", whole, fraction.trim_end_matches('0'), unit)
+}
+
+/// Deserializes an amount of wei given as a JSON number or as a string accepted by
+/// `parse_units`
+pub fn deserialize_units<'de, D: Deserializer<'de>>(deserializer: D) -> Result<U256, D::Error> {
+    deserializer.deserialize_any(UnitsVisitor)
+}
+
+struct UnitsVisitor;
+
+impl<'de> Visitor<'de> for UnitsVisitor {
+    type Value = U256;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an amount such as \"1.5 ether\", \"30 gwei\" or \"0x5208\"")
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<U256, E> {
+        Ok(U256::from(v))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<U256, E> {
+        parse_units(v).map_err(E::custom)
+    }
+}
+
+/// Parses a non-negative decimal number scaled by `10^decimals`, which must come out whole
+fn parse_decimal(number: &str, decimals: usize) -> Result<U256, Error> {
+    let invalid = || Error::InvalidAmount(number.to_string());
+    let (whole, fraction) = match number.find('.') {
+        Some(dot) => (&number[..dot], &number[dot + 1..]),
+        None => (number, "")
+    };
+    if whole.is_empty() || (number.contains('.') && fraction.is_empty()) {
+        return Err(invalid());
+    }
+    if !whole.bytes().chain(fraction.bytes()).all(|b| b.is_ascii_digit()) {
+        return Err(invalid());
+    }
+    // digits past the unit's decimals would be fractions of a wei
+    if fraction.len() > decimals && fraction[decimals..].bytes().any(|b| b != b'0') {
+        return Err(invalid());
+    }
+    let fraction = &fraction[..fraction.len().min(decimals)];
+    let digits = format!("{}{}{}", whole, fraction, "0".repeat(decimals - fraction.len()));
+    let digits = digits.trim_start_matches('0');
+    if digits.is_empty() {
+        return Ok(U256::zero());
+    }
+    U256::from_dec_str(digits).map_err(|_| Error::AmountOverflow(number.to_string()))
+}
+
+mod test {
+    #[test]
+    fn test_parses_units() {
+        use ethereum_types::*;
+        use units::parse_units;
+
+        assert_eq!(Ok(U256::from(1_500_000_000_000_000_000u64)), parse_units("1.5 ether"));
+        assert_eq!(Ok(U256::from(1_500_000_000_000_000_000u64)), parse_units("1.5ETH"));
+        assert_eq!(Ok(U256::from(30_000_000_000u64)), parse_units("30 gwei"));
+        assert_eq!(Ok(U256::from(30_000_000_000u64)), parse_units(" 30 Shannon "));
+        assert_eq!(Ok(U256::from(1)), parse_units("0.000000001 gwei"));
+        assert_eq!(Ok(U256::from(1)), parse_units("0.0000000010 gwei"));
+        assert_eq!(Ok(U256::from(21000)), parse_units("21000"));
+        assert_eq!(Ok(U256::from(21000)), parse_units("21000 wei"));
+        assert_eq!(Ok(U256::from(21000)), parse_units("0x5208"));
+        assert_eq!(Ok(U256::zero()), parse_units("0.0 ether"));
+        assert_eq!(Ok(U256::from(1_000_000)), parse_units("1 mwei"));
+        assert_eq!(Ok(U256::from(1_000_000_000_000u64)), parse_units("1 szabo"));
+        assert_eq!(Ok(U256::from(1_000_000_000_000_000u64)), parse_units("1 finney"));
+        assert_eq!(Ok(U256::from(1000)), parse_units("1 kwei"));
+    }
+
+    #[test]
+    fn test_rejects_invalid_amounts() {
+        use ethereum_types::*;
+        use error::Error;
+        use units::parse_units;
+
+        assert_eq!(Err(Error::InvalidAmount("0.5 wei".to_string())), parse_units("0.5 wei"));
+        assert_eq!(Err(Error::InvalidAmount("0.0000000001 gwei".to_string())), parse_units("0.0000000001 gwei"));
+        assert!(parse_units("").is_err());
+        assert!(parse_units("ether").is_err());
+        assert!(parse_units("-1 ether").is_err());
+        assert!(parse_units("1.2.3 ether").is_err());
+        assert!(parse_units(".5 ether").is_err());
+        assert!(parse_units("5. ether").is_err());
+        assert!(parse_units("1,5 ether").is_err());
+        assert!(parse_units("1 bitcoin").is_err());
+        assert!(parse_units("0xzz").is_err());
+        assert!(parse_units("0x").is_err());
+        assert_eq!(Ok(U256::max_value()), parse_units(&format!("0x00{}", "f".repeat(64))));
+        assert!(parse_units(&format!("0x1{}", "0".repeat(64))).is_err());
+
+        let max = "115792089237316195423570985008687907853269984665640564039457584007913129639935";
+        assert_eq!(Ok(U256::max_value()), parse_units(max));
+        let over = "115792089237316195423570985008687907853269984665640564039457584007913129639936";
+        assert_eq!(Err(Error::AmountOverflow(over.to_string())), parse_units(over));
+        assert_eq!(
+            Err(Error::AmountOverflow("115792089237316195423570985008687907853269984665640564039458 ether".to_string())),
+            parse_units("115792089237316195423570985008687907853269984665640564039458 ether")
+        );
+    }
+
+    #[test]
+    fn test_formats_units() {
+        use ethereum_types::*;
+        use units::{format_units, parse_units, Unit};
+
+        assert_eq!("1.5 ether", format_units(&U256::from(1_500_000_000_000_000_000u64), Unit::Ether));
+        assert_eq!("30 gwei", format_units(&U256::from(30_000_000_000u64), Unit::Gwei));
+        assert_eq!("0.000000001 gwei", format_units(&U256::one(), Unit::Gwei));
+        assert_eq!("0 ether", format_units(&U256::zero(), Unit::Ether));
+        assert_eq!("21000 wei", format_units(&U256::from(21000), Unit::Wei));
+
+        let value = U256::max_value();
+        assert_eq!(Ok(value), parse_units(&format_units(&value, Unit::Ether)));
+    }
+
+    #[test]
+    fn test_deserializes_units() {
+        use ethereum_types::*;
+        use serde_json;
+        use units::deserialize_units;
+
+        #[derive(Deserialize)]
+        struct Config {
+            #[serde(deserialize_with = "deserialize_units")]
+            value: U256
+        }
+        let value = |json: &str| serde_json::from_str::<Config>(json).map(|c| c.value);
+
+        assert_eq!(U256::from(1_500_000_000_000_000_000u64), value(r#"{"value":"1.5 ether"}"#).unwrap());
+        assert_eq!(U256::from(21000), value(r#"{"value":"0x5208"}"#).unwrap());
+        assert_eq!(U256::from(21000), value(r#"{"value":21000}"#).unwrap());
+        assert!(value(r#"{"value":"0.5 wei"}"#).is_err());
+        assert!(value(r#"{"value":-1}"#).is_err());
+        assert!(value(r#"{"value":1.5}"#).is_err());
+    }
+}